@@ -4,7 +4,10 @@ use std::{
     time::Duration,
 };
 
-use bevy_dmabuf::dmatex::{Dmatex, DmatexPlane, Resolution};
+use bevy_dmabuf::dmatex::{
+    ChromaSiting, ColorPrimaries, Dmatex, DmatexPlane, DmatexSync, DmatexTransform, Resolution,
+    TransferFunction, YcbcrEncoding, YcbcrRange,
+};
 use example_usages::TestInterfaceProxy;
 use tokio::{sync::Notify, time::timeout};
 use wlx_capture::{
@@ -59,7 +62,22 @@ async fn main() {
                     y: dmabuf.format.height,
                 },
                 format: dmabuf.format.fourcc.value,
-                flip_y: matches!(dmabuf.format.transform, Transform::Flipped),
+                transform: match dmabuf.format.transform {
+                    Transform::Undefined | Transform::Normal => DmatexTransform::Normal,
+                    Transform::Rotated90 => DmatexTransform::Rotate90,
+                    Transform::Rotated180 => DmatexTransform::Rotate180,
+                    Transform::Rotated270 => DmatexTransform::Rotate270,
+                    Transform::Flipped => DmatexTransform::Flipped,
+                    Transform::Flipped90 => DmatexTransform::Flipped90,
+                    Transform::Flipped180 => DmatexTransform::Flipped180,
+                    Transform::Flipped270 => DmatexTransform::Flipped270,
+                },
+                ycbcr_encoding: YcbcrEncoding::default(),
+                ycbcr_range: YcbcrRange::default(),
+                chroma_siting: ChromaSiting::default(),
+                color_primaries: ColorPrimaries::default(),
+                transfer_function: TransferFunction::default(),
+                sync: DmatexSync::default(),
             });
         }
         notify.notify_one();
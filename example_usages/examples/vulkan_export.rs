@@ -1,6 +1,6 @@
 use ash::{Device, Entry, Instance, vk};
 use bevy_dmabuf::{
-    dmatex::{Dmatex, DmatexPlane, Resolution},
+    dmatex::{Dmatex, DmatexPlane, DmatexTransform, Resolution},
     format_mapping::{get_drm_modifiers, vk_format_to_drm_fourcc},
 };
 use std::{
@@ -41,7 +41,7 @@ async fn main() {
             },
             modifier: image.modifier,
             format: vk_format_to_drm_fourcc(format).unwrap() as u32,
-            flip_y: false,
+            transform: DmatexTransform::Normal,
         })
         .await
         .unwrap();
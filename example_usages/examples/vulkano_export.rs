@@ -1,7 +1,10 @@
 use std::{os::fd::OwnedFd, sync::Arc};
 
 use bevy_dmabuf::{
-    dmatex::{Dmatex, DmatexPlane, Resolution},
+    dmatex::{
+        ChromaSiting, ColorPrimaries, Dmatex, DmatexPlane, DmatexSync, DmatexTransform, Resolution,
+        TransferFunction, YcbcrEncoding, YcbcrRange,
+    },
     format_mapping::vk_format_to_drm_fourcc,
 };
 use example_usages::TestInterfaceProxy;
@@ -132,8 +135,14 @@ async fn main() {
             y: size.y,
         },
         format: vk_format_to_drm_fourcc(vk_format.into()).unwrap() as u32,
-        flip_y: false,
+        transform: DmatexTransform::Normal,
         srgb: true,
+        ycbcr_encoding: YcbcrEncoding::default(),
+        ycbcr_range: YcbcrRange::default(),
+        chroma_siting: ChromaSiting::default(),
+        color_primaries: ColorPrimaries::default(),
+        transfer_function: TransferFunction::default(),
+        sync: DmatexSync::default(),
     };
 
     let data_len = size.x * size.y * 4;
@@ -49,6 +49,7 @@ async fn main() -> AppExit {
     App::new()
         .insert_resource(Receiver(rx.into()))
         .init_resource::<PendingDmatex>()
+        .init_resource::<CurrentDmatex>()
         .add_plugins(add_dmabuf_init_plugin(DefaultPlugins).disable::<PipelinedRenderingPlugin>())
         .add_plugins(DmabufImportPlugin)
         .add_systems(Startup, setup)
@@ -72,12 +73,22 @@ fn import_tex(
     dmatexs: Res<ImportedDmatexs>,
     mut receiv: ResMut<Receiver>,
     mut pending: ResMut<PendingDmatex>,
+    mut current: ResMut<CurrentDmatex>,
     mut images: ResMut<Assets<Image>>,
 ) {
     if let Some(buf) = receiv.0.get_mut().unwrap().try_iter().last() {
         info!("got dmatex");
+        // reuse the handle so the material doesn't have to be updated for every frame
+        if let Some(handle) = current.0.as_ref() {
+            if let Err(err) = dmatexs.replace(&mut images, handle, buf, DmatexUsage::Sampling, None)
+            {
+                error!("error while importing dmatex: {err}");
+            }
+            return;
+        }
         match dmatexs.set(&mut images, buf, DmatexUsage::Sampling, None) {
             Ok(image) => {
+                current.0 = Some(image.clone());
                 pending.0 = Some(image);
             }
             Err(err) => {
@@ -90,6 +101,9 @@ fn import_tex(
 #[derive(Resource, Default)]
 struct PendingDmatex(Option<Handle<Image>>);
 
+#[derive(Resource, Default)]
+struct CurrentDmatex(Option<Handle<Image>>);
+
 // set up a simple 3D scene
 fn setup(
     mut cmds: Commands,
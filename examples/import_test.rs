@@ -114,8 +114,14 @@ fn clone_dmatex(tex: &Dmatex) -> Dmatex {
             .collect(),
         res: tex.res,
         format: tex.format,
-        flip_y: tex.flip_y,
+        transform: tex.transform,
         srgb: tex.srgb,
+        ycbcr_encoding: tex.ycbcr_encoding,
+        ycbcr_range: tex.ycbcr_range,
+        chroma_siting: tex.chroma_siting,
+        color_primaries: tex.color_primaries,
+        transfer_function: tex.transfer_function,
+        sync: tex.sync.try_clone().unwrap(),
     }
 }
 
@@ -34,8 +34,8 @@ use bevy::{
 use bevy_dmabuf::{
     dmatex::{Dmatex, DmatexPlane},
     import::{
-        DmabufImportPlugin, DmatexUsage, DropCallback, ImportedDmatexs, ImportedTexture,
-        import_texture,
+        DmabufImportPlugin, DmatexUsage, DropCallback, ImplicitModifierPolicy, ImportedDmatexs,
+        ImportedTexture, import_texture,
     },
     wgpu_init::add_dmabuf_init_plugin,
 };
@@ -123,8 +123,14 @@ fn clone_dmatex(tex: &Dmatex) -> Dmatex {
             .collect(),
         res: tex.res,
         format: tex.format,
-        flip_y: tex.flip_y,
+        transform: tex.transform,
         srgb: tex.srgb,
+        ycbcr_encoding: tex.ycbcr_encoding,
+        ycbcr_range: tex.ycbcr_range,
+        chroma_siting: tex.chroma_siting,
+        color_primaries: tex.color_primaries,
+        transfer_function: tex.transfer_function,
+        sync: tex.sync.try_clone().unwrap(),
     }
 }
 
@@ -188,6 +194,7 @@ impl TestInterface {
             dmabuf,
             DropCallback(None),
             DmatexUsage::Sampling,
+            ImplicitModifierPolicy::PreferLinear,
         )
         .unwrap();
         _ = self.dmatex_channel.send(Some(tex));
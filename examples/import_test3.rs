@@ -34,8 +34,8 @@ use bevy::{
 use bevy_dmabuf::{
     dmatex::{Dmatex, DmatexPlane},
     import::{
-        DmabufImportPlugin, DmatexUsage, DropCallback, ImportedDmatexs, ImportedTexture,
-        import_texture,
+        DmabufImportPlugin, DmatexUsage, DropCallback, ImplicitModifierPolicy, ImportedDmatexs,
+        ImportedTexture, import_texture,
     },
     wgpu_init::add_dmabuf_init_plugin,
 };
@@ -144,6 +144,7 @@ impl TestInterface {
             dmabuf,
             DropCallback(None),
             DmatexUsage::Sampling,
+            ImplicitModifierPolicy::PreferLinear,
         )
         .unwrap();
         _ = self.dmatex_channel.send(tex);
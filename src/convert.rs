@@ -0,0 +1,552 @@
+use std::num::NonZeroU64;
+
+use bevy::{
+    ecs::{resource::Resource, world::FromWorld},
+    platform::collections::HashMap,
+    render::{
+        render_resource::{
+            BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, RenderPipeline,
+            Sampler, ShaderStages, Texture, TextureView,
+            binding_types::{sampler, texture_2d, uniform_buffer_sized},
+        },
+        renderer::RenderDevice,
+    },
+};
+use wgpu::{
+    SamplerBindingType, TextureFormat, TextureSampleType, TextureUsages, util::BufferInitDescriptor,
+};
+
+use crate::dmatex::{
+    ChromaLocation, ChromaSiting, ColorPrimaries, DmatexTransform, TransferFunction, YcbcrEncoding,
+    YcbcrRange,
+};
+
+/// Parameters for the pass that copies an imported dmatex into a bevy owned texture,
+/// the pass is only used when the dmabuf can't be used as is
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConvertParams {
+    pub transform: DmatexTransform,
+    /// set if the source is a Y′CbCr texture that has to be converted to RGB
+    pub ycbcr: Option<YcbcrParams>,
+    pub primaries: ColorPrimaries,
+    pub transfer: TransferFunction,
+}
+
+/// How the planes of a Y′CbCr source are converted to RGB
+///
+/// the conversion happens in the convert pass instead of a `VkSamplerYcbcrConversion`, a sampler
+/// conversion has to be an immutable sampler of the descriptor set layout, which wgpu and the
+/// bevy material bind groups can't express
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct YcbcrParams {
+    /// 2 for a Y′ and a CbCr plane, 3 for separate Y′, Cb and Cr planes
+    pub plane_count: u32,
+    pub bit_depth: u32,
+    /// horizontal and vertical divisor of the chroma plane resolution
+    pub chroma_subsampling: (u32, u32),
+    pub chroma_siting: ChromaSiting,
+    pub encoding: YcbcrEncoding,
+    pub range: YcbcrRange,
+}
+
+impl YcbcrParams {
+    /// the luma coefficients of red and blue
+    fn kr_kb(self) -> (f32, f32) {
+        match self.encoding {
+            YcbcrEncoding::Bt601 => (0.299, 0.114),
+            YcbcrEncoding::Bt709 => (0.2126, 0.0722),
+            YcbcrEncoding::Bt2020 => (0.2627, 0.0593),
+        }
+    }
+
+    /// offset of the chroma samples relative to the default midpoint siting, in luma texels
+    fn chroma_offset(self) -> [f32; 2] {
+        let offset = |location, subsampling: u32| match location {
+            ChromaLocation::Midpoint => 0.0,
+            ChromaLocation::Cosited => (subsampling - 1) as f32 / 2.0,
+        };
+        [
+            offset(self.chroma_siting.x, self.chroma_subsampling.0),
+            offset(self.chroma_siting.y, self.chroma_subsampling.1),
+        ]
+    }
+
+    /// column major matrix mapping normalized (Y′, Cb, Cr, 1) samples to non linear RGB
+    fn to_rgb_matrix(self) -> [[f32; 4]; 4] {
+        let (kr, kb) = self.kr_kb();
+        let kg = 1.0 - kr - kb;
+        // value range of the samples, 8 bit values scaled to the bit depth
+        let scale = (1u32 << (self.bit_depth - 8)) as f32;
+        let max = ((1u64 << self.bit_depth) - 1) as f32;
+        let (y_offset, y_scale, c_scale) = match self.range {
+            YcbcrRange::Full => (0.0, 1.0, 1.0),
+            YcbcrRange::Limited => (
+                16.0 * scale / max,
+                max / (219.0 * scale),
+                max / (224.0 * scale),
+            ),
+        };
+        let c_offset = 128.0 * scale / max;
+        // rows of the RGB from (Y′, Cb, Cr) matrix, with Cb and Cr centered around 0
+        let rows = [
+            [1.0, 0.0, 2.0 * (1.0 - kr)],
+            [
+                1.0,
+                -2.0 * kb * (1.0 - kb) / kg,
+                -2.0 * kr * (1.0 - kr) / kg,
+            ],
+            [1.0, 2.0 * (1.0 - kb), 0.0],
+        ];
+        let mut columns = [[0.0; 4]; 4];
+        for (i, [y, cb, cr]) in rows.into_iter().enumerate() {
+            columns[0][i] = y * y_scale;
+            columns[1][i] = cb * c_scale;
+            columns[2][i] = cr * c_scale;
+            columns[3][i] = -y * y_scale * y_offset - (cb + cr) * c_scale * c_offset;
+        }
+        columns[3][3] = 1.0;
+        columns
+    }
+}
+
+impl ConvertParams {
+    /// size of the `ConvertParams` struct in convert.wgsl
+    const SHADER_SIZE: u64 = 144;
+
+    pub fn is_identity(&self) -> bool {
+        self.transform == DmatexTransform::Normal
+            && self.ycbcr.is_none()
+            && self.primaries == ColorPrimaries::Bt709
+            && self.shader_transfer() == 0
+    }
+
+    fn is_hdr(&self) -> bool {
+        matches!(self.transfer, TransferFunction::Pq | TransferFunction::Hlg)
+    }
+
+    /// format of the converted texture for a source texture of the given format
+    pub fn output_format(&self, source: TextureFormat) -> TextureFormat {
+        self.fixed_output_format().unwrap_or(source)
+    }
+
+    /// format of the converted texture if it doesn't depend on the format of the source texture
+    pub fn fixed_output_format(&self) -> Option<TextureFormat> {
+        if self.is_hdr() {
+            return Some(TextureFormat::Rgba16Float);
+        }
+        self.ycbcr.map(|ycbcr| match ycbcr.bit_depth > 8 {
+            true => TextureFormat::Rgba16Float,
+            false => TextureFormat::Rgba8UnormSrgb,
+        })
+    }
+
+    /// the transfer function the shader has to decode, matches the constants in convert.wgsl
+    fn shader_transfer(&self) -> u32 {
+        match (self.transfer, self.ycbcr.is_some()) {
+            (TransferFunction::Pq, _) => 2,
+            (TransferFunction::Hlg, _) => 3,
+            // RGB sources are decoded by using an srgb texture format
+            (TransferFunction::Srgb, true) => 1,
+            (TransferFunction::Srgb, false) | (TransferFunction::Linear, _) => 0,
+        }
+    }
+
+    /// column major matrix converting linear RGB with the source primaries to BT.709 primaries
+    fn primaries_matrix(&self) -> [[f32; 3]; 3] {
+        match self.primaries {
+            ColorPrimaries::Bt709 => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            ColorPrimaries::Bt2020 => [
+                [1.6605, -0.1246, -0.0182],
+                [-0.5876, 1.1329, -0.1006],
+                [-0.0728, -0.0083, 1.1187],
+            ],
+        }
+    }
+
+    /// size of the converted texture for a source texture of the given size
+    pub fn output_size(&self, source: wgpu::Extent3d) -> wgpu::Extent3d {
+        match self.transform.swaps_dimensions() {
+            true => wgpu::Extent3d {
+                width: source.height,
+                height: source.width,
+                ..source
+            },
+            false => source,
+        }
+    }
+
+    /// column major matrix mapping centered uvs of the converted texture to centered uvs of the
+    /// source texture, i.e. the inverse of the transform
+    pub(crate) fn uv_matrix(&self) -> [[f32; 2]; 2] {
+        let (quarter_turns, flipped) = self.transform.parts();
+        let source_uv = |mut uv: [f32; 2]| {
+            for _ in 0..quarter_turns {
+                // clockwise quarter turn, uvs are y down
+                uv = [-uv[1], uv[0]];
+            }
+            if flipped {
+                uv[0] = -uv[0];
+            }
+            uv
+        };
+        [source_uv([1.0, 0.0]), source_uv([0.0, 1.0])]
+    }
+
+    /// the params laid out like the `ConvertParams` struct in convert.wgsl
+    fn to_shader_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::SHADER_SIZE as usize);
+        for v in self.uv_matrix().into_iter().flatten() {
+            bytes.extend_from_slice(&v.to_ne_bytes());
+        }
+        let ycbcr_matrix = self
+            .ycbcr
+            .map(YcbcrParams::to_rgb_matrix)
+            .unwrap_or_default();
+        for v in ycbcr_matrix.into_iter().flatten() {
+            bytes.extend_from_slice(&v.to_ne_bytes());
+        }
+        for column in self.primaries_matrix() {
+            for v in column {
+                bytes.extend_from_slice(&v.to_ne_bytes());
+            }
+            // vec3 columns are padded to 16 bytes
+            bytes.extend_from_slice(&[0; 4]);
+        }
+        let chroma_offset = self
+            .ycbcr
+            .map(YcbcrParams::chroma_offset)
+            .unwrap_or_default();
+        for v in chroma_offset {
+            bytes.extend_from_slice(&v.to_ne_bytes());
+        }
+        let plane_count = self.ycbcr.map(|v| v.plane_count).unwrap_or(1);
+        bytes.extend_from_slice(&plane_count.to_ne_bytes());
+        bytes.extend_from_slice(&self.shader_transfer().to_ne_bytes());
+        bytes.resize(Self::SHADER_SIZE as usize, 0);
+        bytes
+    }
+}
+
+/// Render world resource holding the shared state of the conversion pass
+#[derive(Resource)]
+pub struct DmatexConverter {
+    shader: wgpu::ShaderModule,
+    layout: BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    sampler: Sampler,
+    chroma_sampler: Sampler,
+    pipelines: HashMap<TextureFormat, RenderPipeline>,
+}
+
+impl FromWorld for DmatexConverter {
+    fn from_world(world: &mut bevy::ecs::world::World) -> Self {
+        let device = world.resource::<RenderDevice>();
+        let shader = device.create_and_validate_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("dmatex convert shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("convert.wgsl").into()),
+        });
+        let layout = device.create_bind_group_layout(
+            "dmatex convert bind group layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                    sampler(SamplerBindingType::Filtering),
+                    uniform_buffer_sized(false, NonZeroU64::new(ConvertParams::SHADER_SIZE)),
+                ),
+            ),
+        );
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("dmatex convert pipeline layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("dmatex convert sampler"),
+            ..Default::default()
+        });
+        // chroma samples don't line up with the luma samples, so they have to be interpolated
+        let chroma_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("dmatex convert chroma sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        DmatexConverter {
+            shader,
+            layout,
+            pipeline_layout,
+            sampler,
+            chroma_sampler,
+            pipelines: HashMap::default(),
+        }
+    }
+}
+
+/// Bevy owned texture that an imported dmatex gets rendered into every frame
+#[derive(Clone, Debug)]
+pub struct ConvertedTexture {
+    texture: Texture,
+    texture_view: TextureView,
+    bind_group: BindGroup,
+    pipeline: RenderPipeline,
+}
+
+impl ConvertedTexture {
+    pub fn texture(&self) -> Texture {
+        self.texture.clone()
+    }
+    pub fn view(&self) -> TextureView {
+        self.texture_view.clone()
+    }
+}
+
+impl DmatexConverter {
+    fn pipeline(&mut self, device: &RenderDevice, format: TextureFormat) -> RenderPipeline {
+        self.pipelines
+            .entry(format)
+            .or_insert_with(|| {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("dmatex convert pipeline"),
+                    layout: Some(&self.pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &self.shader,
+                        entry_point: Some("vertex"),
+                        compilation_options: Default::default(),
+                        buffers: &[],
+                    },
+                    primitive: wgpu::PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState::default(),
+                    fragment: Some(wgpu::FragmentState {
+                        module: &self.shader,
+                        entry_point: Some("fragment"),
+                        compilation_options: Default::default(),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format,
+                            blend: None,
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    multiview: None,
+                    cache: None,
+                })
+            })
+            .clone()
+    }
+
+    /// creates the texture the `source` will be converted into, `source_views` has one view per
+    /// plane of the source, views of planes the source doesn't have are ignored
+    pub fn create_converted(
+        &mut self,
+        device: &RenderDevice,
+        source: &Texture,
+        source_views: [&TextureView; 3],
+        params: ConvertParams,
+    ) -> ConvertedTexture {
+        let params_buffer = device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("dmatex convert params"),
+            contents: &params.to_shader_bytes(),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("converted dmatex"),
+            size: params.output_size(source.size()),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: params.output_format(source.format()),
+            usage: TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let texture_view = texture.create_view(&Default::default());
+        let bind_group = device.create_bind_group(
+            "dmatex convert bind group",
+            &self.layout,
+            &BindGroupEntries::sequential((
+                source_views[0],
+                source_views[1],
+                source_views[2],
+                &self.sampler,
+                &self.chroma_sampler,
+                params_buffer.as_entire_binding(),
+            )),
+        );
+        ConvertedTexture {
+            pipeline: self.pipeline(device, texture.format()),
+            texture,
+            texture_view,
+            bind_group,
+        }
+    }
+
+    /// records the conversion of the current contents of the imported dmatex
+    pub fn encode(&self, encoder: &mut wgpu::CommandEncoder, converted: &ConvertedTexture) {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("dmatex convert pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &converted.texture_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&converted.pipeline);
+        pass.set_bind_group(0, &*converted.bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSFORMS: [DmatexTransform; 8] = [
+        DmatexTransform::Normal,
+        DmatexTransform::Rotate90,
+        DmatexTransform::Rotate180,
+        DmatexTransform::Rotate270,
+        DmatexTransform::Flipped,
+        DmatexTransform::Flipped90,
+        DmatexTransform::Flipped180,
+        DmatexTransform::Flipped270,
+    ];
+
+    fn uv_matrix(transform: DmatexTransform) -> [[f32; 2]; 2] {
+        ConvertParams {
+            transform,
+            ..Default::default()
+        }
+        .uv_matrix()
+    }
+
+    fn mul(a: [[f32; 2]; 2], b: [[f32; 2]; 2]) -> [[f32; 2]; 2] {
+        b.map(|column| [0, 1].map(|row| a[0][row] * column[0] + a[1][row] * column[1]))
+    }
+
+    #[test]
+    fn uv_matrix_values() {
+        assert_eq!(uv_matrix(DmatexTransform::Normal), [[1.0, 0.0], [0.0, 1.0]]);
+        // the right of the upright texture is the bottom of a buffer that has to be turned
+        // counter-clockwise
+        assert_eq!(
+            uv_matrix(DmatexTransform::Rotate90),
+            [[0.0, 1.0], [-1.0, 0.0]]
+        );
+        assert_eq!(
+            uv_matrix(DmatexTransform::Rotate180),
+            [[-1.0, 0.0], [0.0, -1.0]]
+        );
+        assert_eq!(
+            uv_matrix(DmatexTransform::Flipped),
+            [[-1.0, 0.0], [0.0, 1.0]]
+        );
+        assert_eq!(
+            uv_matrix(DmatexTransform::Flipped180),
+            [[1.0, 0.0], [0.0, -1.0]]
+        );
+    }
+
+    #[test]
+    fn uv_matrix_inverses() {
+        let identity = [[1.0, 0.0], [0.0, 1.0]];
+        assert_eq!(
+            mul(
+                uv_matrix(DmatexTransform::Rotate90),
+                uv_matrix(DmatexTransform::Rotate270)
+            ),
+            identity
+        );
+        for transform in TRANSFORMS {
+            let m = uv_matrix(transform);
+            let det = m[0][0] * m[1][1] - m[1][0] * m[0][1];
+            match transform.parts().1 {
+                // a flip is its own inverse
+                true => {
+                    assert_eq!(det, -1.0, "{transform:?}");
+                    assert_eq!(mul(m, m), identity, "{transform:?}");
+                }
+                false => assert_eq!(det, 1.0, "{transform:?}"),
+            }
+        }
+    }
+
+    fn ycbcr(encoding: YcbcrEncoding, range: YcbcrRange, bit_depth: u32) -> YcbcrParams {
+        YcbcrParams {
+            plane_count: 2,
+            bit_depth,
+            chroma_subsampling: (2, 2),
+            chroma_siting: ChromaSiting::default(),
+            encoding,
+            range,
+        }
+    }
+
+    /// converts samples in the integer range of the bit depth with the matrix
+    fn to_rgb(params: YcbcrParams, [y, cb, cr]: [f32; 3]) -> [f32; 3] {
+        let max = ((1u32 << params.bit_depth) - 1) as f32;
+        let sample = [y / max, cb / max, cr / max, 1.0];
+        let m = params.to_rgb_matrix();
+        [0, 1, 2].map(|row| (0..4).map(|column| m[column][row] * sample[column]).sum())
+    }
+
+    fn assert_rgb(rgb: [f32; 3], expected: [f32; 3]) {
+        assert!(
+            rgb.iter().zip(expected).all(|(a, b)| (a - b).abs() < 2e-3),
+            "{rgb:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn to_rgb_matrix_black_and_white() {
+        for encoding in [
+            YcbcrEncoding::Bt601,
+            YcbcrEncoding::Bt709,
+            YcbcrEncoding::Bt2020,
+        ] {
+            let full = ycbcr(encoding, YcbcrRange::Full, 8);
+            assert_rgb(to_rgb(full, [0.0, 128.0, 128.0]), [0.0; 3]);
+            assert_rgb(to_rgb(full, [255.0, 128.0, 128.0]), [1.0; 3]);
+            let limited = ycbcr(encoding, YcbcrRange::Limited, 8);
+            assert_rgb(to_rgb(limited, [16.0, 128.0, 128.0]), [0.0; 3]);
+            assert_rgb(to_rgb(limited, [235.0, 128.0, 128.0]), [1.0; 3]);
+            let limited_10 = ycbcr(encoding, YcbcrRange::Limited, 10);
+            assert_rgb(to_rgb(limited_10, [64.0, 512.0, 512.0]), [0.0; 3]);
+            assert_rgb(to_rgb(limited_10, [940.0, 512.0, 512.0]), [1.0; 3]);
+        }
+    }
+
+    #[test]
+    fn to_rgb_matrix_primaries() {
+        for encoding in [
+            YcbcrEncoding::Bt601,
+            YcbcrEncoding::Bt709,
+            YcbcrEncoding::Bt2020,
+        ] {
+            let params = ycbcr(encoding, YcbcrRange::Full, 8);
+            let (kr, kb) = params.kr_kb();
+            let kg = 1.0 - kr - kb;
+            // encodes full intensity red, green and blue with the luma coefficients
+            for (rgb, y) in [
+                ([1.0, 0.0, 0.0], kr),
+                ([0.0, 1.0, 0.0], kg),
+                ([0.0, 0.0, 1.0], kb),
+            ] {
+                let cb = (rgb[2] - y) / (2.0 * (1.0 - kb));
+                let cr = (rgb[0] - y) / (2.0 * (1.0 - kr));
+                let sample = [y * 255.0, cb * 255.0 + 128.0, cr * 255.0 + 128.0];
+                assert_rgb(to_rgb(params, sample), rgb);
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+struct ConvertParams {
+    // maps centered uvs of the target to centered uvs of the source
+    uv_transform: mat2x2<f32>,
+    // maps (Y′, Cb, Cr, 1) to non linear RGB
+    ycbcr_to_rgb: mat4x4<f32>,
+    // maps linear RGB to linear RGB with BT.709 primaries
+    primaries: mat3x3<f32>,
+    // offset of the chroma samples in luma texels
+    chroma_offset: vec2<f32>,
+    // 1 for RGB sources, 2 or 3 for Y′CbCr sources
+    plane_count: u32,
+    // one of the TRANSFER_* constants
+    transfer: u32,
+}
+
+const TRANSFER_LINEAR: u32 = 0u;
+const TRANSFER_SRGB: u32 = 1u;
+const TRANSFER_PQ: u32 = 2u;
+const TRANSFER_HLG: u32 = 3u;
+
+@group(0) @binding(0) var src_plane_0: texture_2d<f32>;
+@group(0) @binding(1) var src_plane_1: texture_2d<f32>;
+@group(0) @binding(2) var src_plane_2: texture_2d<f32>;
+@group(0) @binding(3) var src_sampler: sampler;
+@group(0) @binding(4) var chroma_sampler: sampler;
+@group(0) @binding(5) var<uniform> params: ConvertParams;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// fullscreen triangle, uv (0, 0) is the top left of the target
+@vertex
+fn vertex(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = params.uv_transform * (in.uv - 0.5) + 0.5;
+    let plane_0 = textureSampleLevel(src_plane_0, src_sampler, uv, 0.0);
+    var color = plane_0;
+    if params.plane_count != 1u {
+        let chroma_uv = uv + params.chroma_offset / vec2<f32>(textureDimensions(src_plane_0));
+        var cbcr: vec2<f32>;
+        if params.plane_count == 2u {
+            cbcr = textureSampleLevel(src_plane_1, chroma_sampler, chroma_uv, 0.0).rg;
+        } else {
+            cbcr = vec2<f32>(
+                textureSampleLevel(src_plane_1, chroma_sampler, chroma_uv, 0.0).r,
+                textureSampleLevel(src_plane_2, chroma_sampler, chroma_uv, 0.0).r,
+            );
+        }
+        let rgb = saturate((params.ycbcr_to_rgb * vec4<f32>(plane_0.r, cbcr, 1.0)).rgb);
+        color = vec4<f32>(rgb, 1.0);
+    }
+    var rgb = color.rgb;
+    switch params.transfer {
+        case TRANSFER_SRGB: {
+            rgb = srgb_to_linear(rgb);
+        }
+        case TRANSFER_PQ: {
+            rgb = pq_to_linear(rgb);
+        }
+        case TRANSFER_HLG: {
+            rgb = hlg_to_linear(rgb);
+        }
+        default: {}
+    }
+    return vec4<f32>(params.primaries * rgb, color.a);
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+// SMPTE ST 2084 EOTF, scaled so 203 nits (reference white) is 1.0
+fn pq_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let m1 = 0.1593017578125;
+    let m2 = 78.84375;
+    let c1 = 0.8359375;
+    let c2 = 18.8515625;
+    let c3 = 18.6875;
+    let p = pow(saturate(color), vec3<f32>(1.0 / m2));
+    let nits = 10000.0 * pow(max(p - c1, vec3<f32>(0.0)) / (c2 - c3 * p), vec3<f32>(1.0 / m1));
+    return nits / 203.0;
+}
+
+// ARIB STD-B67 inverse OETF without the OOTF, scaled so reference white (75% signal) is 1.0
+fn hlg_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let a = 0.17883277;
+    let b = 0.28466892;
+    let c = 0.55991073;
+    let reference_white = 0.26496256;
+    let signal = saturate(color);
+    let low = signal * signal / 3.0;
+    let high = (exp((signal - c) / a) + b) / 12.0;
+    return select(high, low, signal <= vec3<f32>(0.5)) / reference_white;
+}
@@ -0,0 +1,350 @@
+use std::{
+    io,
+    os::fd::{AsFd as _, AsRawFd as _, OwnedFd},
+    ptr::NonNull,
+};
+
+use bevy::render::{
+    render_resource::{Texture, TextureView},
+    renderer::{RenderDevice, RenderQueue},
+};
+use drm_fourcc::DrmFourcc;
+use wgpu::{TextureFormat, TextureUsages};
+
+use crate::{
+    convert::ConvertParams,
+    dmatex::{Dmatex, Resolution},
+    format_table::DRM_FORMAT_MOD_LINEAR,
+    import::{DmatexInfo, DropCallback, ImportError},
+    sync_file::{self, DMA_BUF_SYNC_END, DMA_BUF_SYNC_READ, DMA_BUF_SYNC_START},
+    validate::validate_dmatex,
+};
+
+/// layout of a packed 8 bit RGB pixel
+#[derive(Clone, Copy, Debug)]
+struct CpuFormat {
+    bytes_per_pixel: usize,
+    /// byte offsets of the r, g and b channels
+    rgb: [usize; 3],
+    alpha: Option<usize>,
+}
+
+/// drm fourccs are little endian, so e.g. Argb8888 is stored as B, G, R, A
+fn cpu_format(fourcc: DrmFourcc) -> Option<CpuFormat> {
+    let format = |bytes_per_pixel, rgb, alpha| CpuFormat {
+        bytes_per_pixel,
+        rgb,
+        alpha,
+    };
+    Some(match fourcc {
+        DrmFourcc::Argb8888 => format(4, [2, 1, 0], Some(3)),
+        DrmFourcc::Xrgb8888 => format(4, [2, 1, 0], None),
+        DrmFourcc::Abgr8888 => format(4, [0, 1, 2], Some(3)),
+        DrmFourcc::Xbgr8888 => format(4, [0, 1, 2], None),
+        DrmFourcc::Rgba8888 => format(4, [3, 2, 1], Some(0)),
+        DrmFourcc::Rgbx8888 => format(4, [3, 2, 1], None),
+        DrmFourcc::Bgra8888 => format(4, [1, 2, 3], Some(0)),
+        DrmFourcc::Bgrx8888 => format(4, [1, 2, 3], None),
+        DrmFourcc::Rgb888 => format(3, [2, 1, 0], None),
+        DrmFourcc::Bgr888 => format(3, [0, 1, 2], None),
+        _ => return None,
+    })
+}
+
+/// where the pixels of a LINEAR dmatex are in its dmabuf
+#[derive(Clone, Copy, Debug)]
+struct CpuLayout {
+    format: CpuFormat,
+    offset: usize,
+    stride: usize,
+    res: Resolution,
+}
+
+impl CpuLayout {
+    /// converts the pixels to RGBA and applies the transform, sampling the source pixel at the
+    /// center of every output pixel like the convert pass does
+    fn to_rgba(self, bytes: &[u8], convert_params: &ConvertParams) -> Vec<u8> {
+        let [[m00, m01], [m10, m11]] = convert_params.uv_matrix();
+        let size = convert_params.output_size(wgpu::Extent3d {
+            width: self.res.x,
+            height: self.res.y,
+            depth_or_array_layers: 1,
+        });
+        let (width, height) = (size.width as usize, size.height as usize);
+        let (src_width, src_height) = (self.res.x as usize, self.res.y as usize);
+        let mut rgba = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            let v = (y as f32 + 0.5) / height as f32 - 0.5;
+            for x in 0..width {
+                let u = (x as f32 + 0.5) / width as f32 - 0.5;
+                let src_u = m00 * u + m10 * v + 0.5;
+                let src_v = m01 * u + m11 * v + 0.5;
+                let src_x = ((src_u * src_width as f32) as usize).min(src_width - 1);
+                let src_y = ((src_v * src_height as f32) as usize).min(src_height - 1);
+                let pixel = self.offset + src_y * self.stride + src_x * self.format.bytes_per_pixel;
+                let pixel = &bytes[pixel..pixel + self.format.bytes_per_pixel];
+                rgba.extend(self.format.rgb.map(|i| pixel[i]));
+                rgba.push(self.format.alpha.map_or(u8::MAX, |i| pixel[i]));
+            }
+        }
+        rgba
+    }
+}
+
+/// format of the texture a dmatex gets uploaded into, None if the format can't be converted on the
+/// cpu
+pub fn cpu_upload_format(buf: &Dmatex) -> Option<TextureFormat> {
+    cpu_format(DrmFourcc::try_from(buf.format).ok()?)?;
+    Some(match buf.srgb {
+        true => TextureFormat::Rgba8UnormSrgb,
+        false => TextureFormat::Rgba8Unorm,
+    })
+}
+
+/// a dmabuf mapped into memory
+#[derive(Debug)]
+struct MappedDmabuf {
+    fd: OwnedFd,
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+// the mapping is only read through `&self` and unmapped on drop
+unsafe impl Send for MappedDmabuf {}
+unsafe impl Sync for MappedDmabuf {}
+
+impl MappedDmabuf {
+    /// `len` has to be within the dmabuf, see [`validate_dmatex`]
+    fn new(fd: OwnedFd, len: usize) -> Result<MappedDmabuf, ImportError> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                fd.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(ImportError::MmapFailed(last_errno()));
+        }
+        let ptr = NonNull::new(ptr.cast()).ok_or(ImportError::MmapFailed(libc::EINVAL))?;
+        Ok(MappedDmabuf { fd, ptr, len })
+    }
+
+    fn sync(&self, flags: u64) -> Result<(), ImportError> {
+        sync_file::sync(self.fd.as_fd(), flags)
+            .map_err(|err| ImportError::DmabufSyncFailed(err.raw_os_error().unwrap_or(libc::EIO)))
+    }
+
+    /// calls `f` with the contents of the dmabuf, the access is bracketed by DMA_BUF_IOCTL_SYNC
+    fn read<T>(&self, f: impl FnOnce(&[u8]) -> T) -> Result<T, ImportError> {
+        self.sync(DMA_BUF_SYNC_START | u64::from(DMA_BUF_SYNC_READ))?;
+        let bytes = unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) };
+        let out = f(bytes);
+        self.sync(DMA_BUF_SYNC_END | u64::from(DMA_BUF_SYNC_READ))?;
+        Ok(out)
+    }
+}
+
+impl Drop for MappedDmabuf {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr.as_ptr().cast(), self.len);
+        }
+    }
+}
+
+fn last_errno() -> i32 {
+    io::Error::last_os_error()
+        .raw_os_error()
+        .unwrap_or(libc::EIO)
+}
+
+/// fallback for LINEAR dmatexs the gpu can't import, the dmabuf is mapped and its contents are
+/// converted to RGBA and uploaded every frame. Only the transform of the dmatex is applied, the
+/// values are expected to be sRGB
+#[derive(Debug)]
+pub struct CpuUploadedTexture {
+    mapping: MappedDmabuf,
+    layout: CpuLayout,
+    convert_params: ConvertParams,
+    texture: Texture,
+    texture_view: TextureView,
+    info: DmatexInfo,
+    _on_drop: DropCallback,
+}
+
+impl CpuUploadedTexture {
+    pub fn new(
+        device: &RenderDevice,
+        buf: Dmatex,
+        on_drop: DropCallback,
+    ) -> Result<CpuUploadedTexture, ImportError> {
+        let info = DmatexInfo::new(&buf);
+        validate_dmatex(&buf).map_err(|err| err.error)?;
+        let format = cpu_format(DrmFourcc::try_from(buf.format)?)
+            .ok_or(ImportError::CpuIncompatibleFormat)?;
+        let texture_format = cpu_upload_format(&buf).ok_or(ImportError::CpuIncompatibleFormat)?;
+        let [plane] = <[_; 1]>::try_from(buf.planes).map_err(|planes| match planes.is_empty() {
+            true => ImportError::NoPlanes,
+            false => ImportError::IncorrectNumberOfPlanes,
+        })?;
+        if plane.modifier != DRM_FORMAT_MOD_LINEAR {
+            return Err(ImportError::NotLinear);
+        }
+        let layout = CpuLayout {
+            format,
+            offset: plane.offset as usize,
+            stride: plane.stride as usize,
+            res: buf.res,
+        };
+        if layout.stride < buf.res.x as usize * format.bytes_per_pixel {
+            return Err(ImportError::PlaneLayoutMismatch);
+        }
+        let len = layout.offset + layout.stride * buf.res.y as usize;
+        let mapping = MappedDmabuf::new(plane.dmabuf_fd.into(), len)?;
+        let convert_params = ConvertParams {
+            transform: buf.transform,
+            ..Default::default()
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("cpu uploaded dmatex"),
+            size: convert_params.output_size(wgpu::Extent3d {
+                width: buf.res.x,
+                height: buf.res.y,
+                depth_or_array_layers: 1,
+            }),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: texture_format,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let texture_view = texture.create_view(&Default::default());
+        Ok(CpuUploadedTexture {
+            mapping,
+            layout,
+            convert_params,
+            texture,
+            texture_view,
+            info,
+            _on_drop: on_drop,
+        })
+    }
+    pub fn texture(&self) -> Texture {
+        self.texture.clone()
+    }
+    pub fn view(&self) -> TextureView {
+        self.texture_view.clone()
+    }
+    pub fn info(&self) -> DmatexInfo {
+        self.info.clone()
+    }
+
+    /// copies the current contents of the dmabuf into the texture
+    pub fn upload(&self, queue: &RenderQueue) -> Result<(), ImportError> {
+        let size = self.texture.size();
+        let rgba = self
+            .mapping
+            .read(|bytes| self.layout.to_rgba(bytes, &self.convert_params))?;
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &rgba,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size.width * 4),
+                rows_per_image: Some(size.height),
+            },
+            size,
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dmatex::DmatexTransform;
+
+    #[expect(clippy::unwrap_used)]
+    fn layout(fourcc: DrmFourcc, offset: usize, stride: usize, res: Resolution) -> CpuLayout {
+        CpuLayout {
+            format: cpu_format(fourcc).unwrap(),
+            offset,
+            stride,
+            res,
+        }
+    }
+
+    fn transformed(transform: DmatexTransform) -> ConvertParams {
+        ConvertParams {
+            transform,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn channel_order() {
+        let res = Resolution { x: 1, y: 1 };
+        let pixel = [1, 2, 3, 4];
+        for (fourcc, rgba) in [
+            (DrmFourcc::Argb8888, [3, 2, 1, 4]),
+            (DrmFourcc::Xrgb8888, [3, 2, 1, u8::MAX]),
+            (DrmFourcc::Abgr8888, [1, 2, 3, 4]),
+            (DrmFourcc::Rgba8888, [4, 3, 2, 1]),
+            (DrmFourcc::Bgrx8888, [2, 3, 4, u8::MAX]),
+            (DrmFourcc::Rgb888, [3, 2, 1, u8::MAX]),
+            (DrmFourcc::Bgr888, [1, 2, 3, u8::MAX]),
+        ] {
+            assert_eq!(
+                layout(fourcc, 0, 4, res).to_rgba(&pixel, &Default::default()),
+                rgba,
+                "{fourcc:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn offset_and_stride() {
+        // 2x2 RGB888 with a 2 byte offset and 2 bytes of padding after every row
+        let bytes = [
+            0, 0, //
+            1, 1, 1, 2, 2, 2, 0, 0, //
+            3, 3, 3, 4, 4, 4, 0, 0,
+        ];
+        let layout = layout(DrmFourcc::Rgb888, 2, 8, Resolution { x: 2, y: 2 });
+        let rgba = layout.to_rgba(&bytes, &Default::default());
+        assert_eq!(
+            rgba.chunks(4).map(|pixel| pixel[0]).collect::<Vec<_>>(),
+            [1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn transforms() {
+        // 2x1 Abgr8888, the red channel numbers the pixels
+        let bytes = [1, 0, 0, 0, 2, 0, 0, 0];
+        let layout = layout(DrmFourcc::Abgr8888, 0, 8, Resolution { x: 2, y: 1 });
+        let red = |transform| {
+            layout
+                .to_rgba(&bytes, &transformed(transform))
+                .chunks(4)
+                .map(|pixel| pixel[0])
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(red(DmatexTransform::Normal), [1, 2]);
+        assert_eq!(red(DmatexTransform::Flipped), [2, 1]);
+        assert_eq!(red(DmatexTransform::Rotate180), [2, 1]);
+        // the output is 1x2, the right of the buffer ends up at the top
+        assert_eq!(red(DmatexTransform::Rotate90), [2, 1]);
+        assert_eq!(red(DmatexTransform::Rotate270), [1, 2]);
+    }
+}
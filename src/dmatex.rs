@@ -1,3 +1,5 @@
+use std::{io, os::fd::AsFd as _};
+
 use zvariant::{self, OwnedFd};
 
 /// Dmabuf Backed Texture
@@ -6,13 +8,221 @@ pub struct Dmatex {
     pub planes: Vec<DmatexPlane>,
     pub res: Resolution,
     pub format: u32,
-    /// TODO: implement this, or remove it
-    pub flip_y: bool,
+    /// how the buffer contents have to be transformed to be upright, the imported texture will
+    /// already have this transform applied
+    pub transform: DmatexTransform,
     /// if the format has an srgb version, use that
     pub srgb: bool,
+    /// matrix used to convert Y′CbCr formats to RGB, ignored for RGB formats
+    pub ycbcr_encoding: YcbcrEncoding,
+    /// value range of Y′CbCr formats, ignored for RGB formats
+    pub ycbcr_range: YcbcrRange,
+    /// position of the chroma samples of subsampled Y′CbCr formats, ignored for other formats
+    pub chroma_siting: ChromaSiting,
+    pub color_primaries: ColorPrimaries,
+    pub transfer_function: TransferFunction,
+    pub sync: DmatexSync,
+}
+
+impl Dmatex {
+    /// duplicates the dmabuf fds, the clone refers to the same buffers
+    pub fn try_clone(&self) -> io::Result<Dmatex> {
+        Ok(Dmatex {
+            planes: self
+                .planes
+                .iter()
+                .map(DmatexPlane::try_clone)
+                .collect::<io::Result<_>>()?,
+            sync: self.sync.try_clone()?,
+            ..*self
+        })
+    }
+}
+
+/// explicit synchronization in the linux-drm-syncobj-v1 model, None uses the implicit fences of
+/// the dmabufs. Sent as an array with at most one element
+#[derive(Debug, Default)]
+pub struct DmatexSync(pub Option<DmatexSyncPoints>);
+
+impl DmatexSync {
+    pub fn try_clone(&self) -> io::Result<DmatexSync> {
+        Ok(DmatexSync(
+            self.0
+                .as_ref()
+                .map(DmatexSyncPoints::try_clone)
+                .transpose()?,
+        ))
+    }
+}
+
+impl zvariant::Type for DmatexSync {
+    const SIGNATURE: &'static zvariant::Signature =
+        &zvariant::Signature::static_array(DmatexSyncPoints::SIGNATURE);
+}
+
+impl serde::Serialize for DmatexSync {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.0)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for DmatexSync {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let points = Vec::<DmatexSyncPoints>::deserialize(deserializer)?;
+        if points.len() > 1 {
+            return Err(serde::de::Error::invalid_length(
+                points.len(),
+                &"at most one element",
+            ));
+        }
+        Ok(DmatexSync(points.into_iter().next()))
+    }
+}
+
+/// the gpu waits for the acquire point before using the dmatex, the release point is signaled
+/// once the dmatex isn't used anymore. The syncobjs are imported as vulkan timeline semaphores,
+/// which requires VK_KHR_external_semaphore_fd and a driver that implements OPAQUE_FD semaphores
+/// as drm syncobjs, like the mesa drivers. On other drivers the sync points are ignored and an
+/// error is logged
+#[derive(Debug, serde::Serialize, serde::Deserialize, zvariant::Type)]
+pub struct DmatexSyncPoints {
+    pub acquire: DmatexTimelinePoint,
+    pub release: DmatexTimelinePoint,
+}
+
+impl DmatexSyncPoints {
+    pub fn try_clone(&self) -> io::Result<DmatexSyncPoints> {
+        Ok(DmatexSyncPoints {
+            acquire: self.acquire.try_clone()?,
+            release: self.release.try_clone()?,
+        })
+    }
+}
+
+/// a point on the timeline of a drm syncobj
+#[derive(Debug, serde::Serialize, serde::Deserialize, zvariant::Type)]
+pub struct DmatexTimelinePoint {
+    pub syncobj_fd: OwnedFd,
+    pub point: u64,
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, zvariant::Type, Copy, Clone)]
+impl DmatexTimelinePoint {
+    pub fn try_clone(&self) -> io::Result<DmatexTimelinePoint> {
+        Ok(DmatexTimelinePoint {
+            syncobj_fd: self.syncobj_fd.as_fd().try_clone_to_owned()?.into(),
+            point: self.point,
+        })
+    }
+}
+
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, zvariant::Type, Copy, Clone, Default, PartialEq, Eq,
+)]
+pub enum YcbcrEncoding {
+    Bt601,
+    #[default]
+    Bt709,
+    Bt2020,
+}
+
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, zvariant::Type, Copy, Clone, Default, PartialEq, Eq,
+)]
+pub enum YcbcrRange {
+    /// Y′ in 16..=235 and CbCr in 16..=240 for 8 bit formats
+    #[default]
+    Limited,
+    Full,
+}
+
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, zvariant::Type, Copy, Clone, Default, PartialEq, Eq,
+)]
+pub struct ChromaSiting {
+    pub x: ChromaLocation,
+    pub y: ChromaLocation,
+}
+
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, zvariant::Type, Copy, Clone, Default, PartialEq, Eq,
+)]
+pub enum ChromaLocation {
+    /// chroma samples are centered between the luma samples they cover
+    #[default]
+    Midpoint,
+    /// chroma samples are at the same position as the first luma sample they cover
+    Cosited,
+}
+
+/// the primaries of the RGB values, imported textures always use BT.709 primaries like the rest
+/// of bevy
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, zvariant::Type, Copy, Clone, Default, PartialEq, Eq,
+)]
+pub enum ColorPrimaries {
+    /// same as sRGB
+    #[default]
+    Bt709,
+    Bt2020,
+}
+
+/// how the (non linear) RGB values are encoded, imported textures are always linear like the
+/// rest of bevy
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, zvariant::Type, Copy, Clone, Default, PartialEq, Eq,
+)]
+pub enum TransferFunction {
+    /// for RGB formats the values are only decoded if [`Dmatex::srgb`] is set
+    #[default]
+    Srgb,
+    Linear,
+    /// SMPTE ST 2084, 203 nits are mapped to 1.0
+    Pq,
+    /// ARIB STD-B67, reference white is mapped to 1.0
+    Hlg,
+}
+
+/// wl_output style transform, rotations are counter-clockwise and flips happen around the
+/// vertical axis before rotating
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, zvariant::Type, Copy, Clone, Default, PartialEq, Eq,
+)]
+pub enum DmatexTransform {
+    #[default]
+    Normal,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    Flipped,
+    Flipped90,
+    /// the buffer is stored upside down
+    Flipped180,
+    Flipped270,
+}
+
+impl DmatexTransform {
+    /// number of counter-clockwise quarter turns and if the buffer is flipped before rotating
+    pub fn parts(self) -> (u8, bool) {
+        match self {
+            DmatexTransform::Normal => (0, false),
+            DmatexTransform::Rotate90 => (1, false),
+            DmatexTransform::Rotate180 => (2, false),
+            DmatexTransform::Rotate270 => (3, false),
+            DmatexTransform::Flipped => (0, true),
+            DmatexTransform::Flipped90 => (1, true),
+            DmatexTransform::Flipped180 => (2, true),
+            DmatexTransform::Flipped270 => (3, true),
+        }
+    }
+    /// if the width and height of the transformed buffer are swapped
+    pub fn swaps_dimensions(self) -> bool {
+        self.parts().0 % 2 == 1
+    }
+}
+
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, zvariant::Type, Copy, Clone, PartialEq, Eq,
+)]
 pub struct Resolution {
     pub x: u32,
     pub y: u32,
@@ -25,3 +235,12 @@ pub struct DmatexPlane {
     pub offset: u32,
     pub stride: i32,
 }
+
+impl DmatexPlane {
+    pub fn try_clone(&self) -> io::Result<DmatexPlane> {
+        Ok(DmatexPlane {
+            dmabuf_fd: self.dmabuf_fd.as_fd().try_clone_to_owned()?.into(),
+            ..*self
+        })
+    }
+}
@@ -80,6 +80,105 @@ pub fn get_drm_image_modifier_info(
     Some(properties.image_format_properties)
 }
 
+/// properties of an image with non drm modifier `tiling` that is imported from a dmabuf, None if
+/// the driver can't import dmabufs with that tiling
+pub fn get_external_image_info(
+    instance: &ash::Instance,
+    physical_device: vk::PhysicalDevice,
+    format: vk::Format,
+    image_type: vk::ImageType,
+    usage: vk::ImageUsageFlags,
+    flags: vk::ImageCreateFlags,
+    tiling: vk::ImageTiling,
+) -> Option<ImageFormatProperties> {
+    let mut external_info = vk::PhysicalDeviceExternalImageFormatInfo::default()
+        .handle_type(vk::ExternalMemoryHandleTypeFlags::DMA_BUF_EXT);
+    let image_format_info = vk::PhysicalDeviceImageFormatInfo2::default()
+        .format(format)
+        .ty(image_type)
+        .usage(usage)
+        .flags(flags)
+        .tiling(tiling)
+        .push_next(&mut external_info);
+    let mut external_properties = vk::ExternalImageFormatProperties::default();
+    let mut properties = vk::ImageFormatProperties2::default().push_next(&mut external_properties);
+    unsafe {
+        match instance.get_physical_device_image_format_properties2(
+            physical_device,
+            &image_format_info,
+            &mut properties,
+        ) {
+            Ok(_) => {}
+            Err(vk::Result::ERROR_FORMAT_NOT_SUPPORTED) => return None,
+            Err(err) => {
+                error!("failed to get format properties: {err}");
+                return None;
+            }
+        };
+    }
+    let image_format_properties = properties.image_format_properties;
+    external_properties
+        .external_memory_properties
+        .external_memory_features
+        .contains(vk::ExternalMemoryFeatureFlags::IMPORTABLE)
+        .then_some(image_format_properties)
+}
+
+/// every fourcc [`drm_fourcc_to_vk_format`] has a vulkan format for
+pub const MAPPED_DRM_FOURCCS: &[drm_fourcc::DrmFourcc] = {
+    use drm_fourcc::DrmFourcc as D;
+    &[
+        D::Abgr1555,
+        D::Xbgr1555,
+        D::Abgr2101010,
+        D::Xbgr2101010,
+        D::Abgr4444,
+        D::Xbgr4444,
+        D::Abgr8888,
+        D::Xbgr8888,
+        D::Argb1555,
+        D::Xrgb1555,
+        D::Argb2101010,
+        D::Xrgb2101010,
+        D::Argb4444,
+        D::Xrgb4444,
+        D::Argb8888,
+        D::Xrgb8888,
+        D::Bgr565,
+        D::Bgr888,
+        D::Bgr888_a8,
+        D::Bgra4444,
+        D::Bgrx4444,
+        D::Bgra5551,
+        D::Bgrx5551,
+        D::Bgra8888,
+        D::Bgrx8888,
+        D::R16,
+        D::R8,
+        D::Rg1616,
+        D::Rg88,
+        D::Rgb565,
+        D::Rgb888,
+        D::Rgb888_a8,
+        D::Rgba4444,
+        D::Rgbx4444,
+        D::Rgba5551,
+        D::Rgbx5551,
+        D::Rgba8888,
+        D::Rgbx8888,
+        D::Nv12,
+        D::Nv16,
+        D::Nv24,
+        D::P010,
+        D::P012,
+        D::P016,
+        D::P210,
+        D::Yuv420,
+        D::Yuv422,
+        D::Yuv444,
+    ]
+};
+
 pub fn drm_fourcc_to_vk_format(drm_format: drm_fourcc::DrmFourcc) -> Option<vk::Format> {
     use drm_fourcc::DrmFourcc as D;
     use vk::Format as F;
@@ -108,6 +207,17 @@ pub fn drm_fourcc_to_vk_format(drm_format: drm_fourcc::DrmFourcc) -> Option<vk::
         D::Rgba4444 | D::Rgbx4444 => F::R4G4B4A4_UNORM_PACK16,
         D::Rgba5551 | D::Rgbx5551 => F::R5G5B5A1_UNORM_PACK16,
         D::Rgba8888 | D::Rgbx8888 => F::R8G8B8A8_UNORM,
+
+        D::Nv12 => F::G8_B8R8_2PLANE_420_UNORM,
+        D::Nv16 => F::G8_B8R8_2PLANE_422_UNORM,
+        D::Nv24 => F::G8_B8R8_2PLANE_444_UNORM,
+        D::P010 => F::G10X6_B10X6R10X6_2PLANE_420_UNORM_3PACK16,
+        D::P012 => F::G12X4_B12X4R12X4_2PLANE_420_UNORM_3PACK16,
+        D::P016 => F::G16_B16R16_2PLANE_420_UNORM,
+        D::P210 => F::G10X6_B10X6R10X6_2PLANE_422_UNORM_3PACK16,
+        D::Yuv420 => F::G8_B8_R8_3PLANE_420_UNORM,
+        D::Yuv422 => F::G8_B8_R8_3PLANE_422_UNORM,
+        D::Yuv444 => F::G8_B8_R8_3PLANE_444_UNORM,
         _ => return None,
     })
 }
@@ -135,7 +245,55 @@ pub fn vk_format_to_drm_fourcc(vk_format: vk::Format) -> Option<drm_fourcc::DrmF
         F::R5G6B5_UNORM_PACK16 => D::Rgb565,
         F::R4G4B4A4_UNORM_PACK16 => D::Rgba4444,
         F::R5G5B5A1_UNORM_PACK16 => D::Rgba5551,
+
+        F::G8_B8R8_2PLANE_420_UNORM => D::Nv12,
+        F::G8_B8R8_2PLANE_422_UNORM => D::Nv16,
+        F::G8_B8R8_2PLANE_444_UNORM => D::Nv24,
+        F::G10X6_B10X6R10X6_2PLANE_420_UNORM_3PACK16 => D::P010,
+        F::G12X4_B12X4R12X4_2PLANE_420_UNORM_3PACK16 => D::P012,
+        F::G16_B16R16_2PLANE_420_UNORM => D::P016,
+        F::G10X6_B10X6R10X6_2PLANE_422_UNORM_3PACK16 => D::P210,
+        F::G8_B8_R8_3PLANE_420_UNORM => D::Yuv420,
+        F::G8_B8_R8_3PLANE_422_UNORM => D::Yuv422,
+        F::G8_B8_R8_3PLANE_444_UNORM => D::Yuv444,
+        _ => return None,
+    })
+}
+
+/// Layout of a multi-planar Y′CbCr format
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct YcbcrFormatInfo {
+    /// single plane formats compatible with each plane, the first plane holds Y′, the following
+    /// planes hold CbCr or Cb and Cr
+    pub planes: &'static [vk::Format],
+    /// horizontal and vertical divisor of the chroma plane resolution
+    pub chroma_subsampling: (u32, u32),
+    /// bits per component that hold the value
+    pub bit_depth: u32,
+}
+
+pub fn vk_format_ycbcr_info(vk_format: vk::Format) -> Option<YcbcrFormatInfo> {
+    use vk::Format as F;
+    const TWO_PLANE_8: &[F] = &[F::R8_UNORM, F::R8G8_UNORM];
+    const TWO_PLANE_16: &[F] = &[F::R16_UNORM, F::R16G16_UNORM];
+    const THREE_PLANE_8: &[F] = &[F::R8_UNORM, F::R8_UNORM, F::R8_UNORM];
+    let (planes, chroma_subsampling, bit_depth) = match vk_format {
+        F::G8_B8R8_2PLANE_420_UNORM => (TWO_PLANE_8, (2, 2), 8),
+        F::G8_B8R8_2PLANE_422_UNORM => (TWO_PLANE_8, (2, 1), 8),
+        F::G8_B8R8_2PLANE_444_UNORM => (TWO_PLANE_8, (1, 1), 8),
+        F::G10X6_B10X6R10X6_2PLANE_420_UNORM_3PACK16 => (TWO_PLANE_16, (2, 2), 10),
+        F::G12X4_B12X4R12X4_2PLANE_420_UNORM_3PACK16 => (TWO_PLANE_16, (2, 2), 12),
+        F::G16_B16R16_2PLANE_420_UNORM => (TWO_PLANE_16, (2, 2), 16),
+        F::G10X6_B10X6R10X6_2PLANE_422_UNORM_3PACK16 => (TWO_PLANE_16, (2, 1), 10),
+        F::G8_B8_R8_3PLANE_420_UNORM => (THREE_PLANE_8, (2, 2), 8),
+        F::G8_B8_R8_3PLANE_422_UNORM => (THREE_PLANE_8, (2, 1), 8),
+        F::G8_B8_R8_3PLANE_444_UNORM => (THREE_PLANE_8, (1, 1), 8),
         _ => return None,
+    };
+    Some(YcbcrFormatInfo {
+        planes,
+        chroma_subsampling,
+        bit_depth,
     })
 }
 
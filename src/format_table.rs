@@ -0,0 +1,555 @@
+use std::{fmt::Display, slice};
+
+use ash::vk::{self, FormatFeatureFlags2};
+use bevy::{ecs::resource::Resource, render::renderer::RenderDevice};
+use drm_fourcc::DrmFourcc;
+use wgpu::{TextureUsages, hal::vulkan::Api as Vulkan};
+
+use crate::{
+    dmatex::Resolution,
+    format_mapping::{
+        MAPPED_DRM_FOURCCS, drm_fourcc_to_vk_format, get_drm_image_modifier_info,
+        get_drm_modifiers, vk_format_ycbcr_info,
+    },
+    import::{
+        DmatexUsage, FourccName, ImplicitModifierPolicy, ImportError, get_image_flags,
+        get_linear_image_info, has_drm_format_modifier_support, resolve_implicit_modifier,
+    },
+    wgpu_init::vulkan_to_wgpu,
+};
+
+pub const DRM_FORMAT_MOD_LINEAR: u64 = 0;
+/// the modifier is implied by the driver, imported as LINEAR or with the driver's own tiling, see
+/// [`ImplicitModifierPolicy`]
+pub const DRM_FORMAT_MOD_INVALID: u64 = 0x00ff_ffff_ffff_ffff;
+
+/// Every (fourcc, modifier) pair the render device can import for [`DmatexUsage::Sampling`],
+/// similar to the format table of linux-dmabuf feedback, meant to be sent to producers
+#[derive(Resource, Clone, Debug, Default, serde::Serialize, serde::Deserialize, zvariant::Type)]
+pub struct DmabufFormatTable {
+    pub formats: Vec<DmabufFormat>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, zvariant::Type)]
+pub struct DmabufFormat {
+    pub fourcc: u32,
+    pub modifiers: Vec<DmabufModifier>,
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize, zvariant::Type)]
+pub struct DmabufModifier {
+    pub modifier: u64,
+    /// number of memory planes a dmabuf with this modifier has
+    pub plane_count: u32,
+    /// the largest resolution a dmabuf with this modifier can be imported with
+    pub max_extent: Resolution,
+}
+
+impl DmabufFormatTable {
+    /// queries the supported formats of the device, returns None if the device isn't a vulkan
+    /// device. [`DRM_FORMAT_MOD_INVALID`] is listed if it can be imported with `implicit_modifier`
+    pub fn new(
+        device: &RenderDevice,
+        implicit_modifier: ImplicitModifierPolicy,
+    ) -> Option<DmabufFormatTable> {
+        unsafe {
+            device.wgpu_device().as_hal::<Vulkan, _, _>(|dev| {
+                let dev = dev?;
+                let instance = dev.shared_instance().raw_instance();
+                let physical_device = dev.raw_physical_device();
+                let modifier_support = has_drm_format_modifier_support(dev);
+                let formats = MAPPED_DRM_FOURCCS
+                    .iter()
+                    .filter_map(|fourcc| {
+                        let vulkan_format = drm_fourcc_to_vk_format(*fourcc)?;
+                        // Y′CbCr formats are converted to RGB, every other format has to be
+                        // usable by wgpu directly
+                        if vk_format_ycbcr_info(vulkan_format).is_none()
+                            && vulkan_to_wgpu(vulkan_format).is_none()
+                        {
+                            return None;
+                        }
+                        if !modifier_support {
+                            return get_linear_only_format(
+                                instance,
+                                physical_device,
+                                *fourcc as u32,
+                                vulkan_format,
+                            );
+                        }
+                        let (_format_properties, drm_format_properties) =
+                            get_drm_modifiers(instance, physical_device, vulkan_format);
+                        let mut modifiers = drm_format_properties
+                            .iter()
+                            .filter(|v| {
+                                v.drm_format_modifier_tiling_features
+                                    .contains(FormatFeatureFlags2::SAMPLED_IMAGE)
+                            })
+                            .filter_map(|v| {
+                                let disjoint = v
+                                    .drm_format_modifier_tiling_features
+                                    .contains(FormatFeatureFlags2::DISJOINT_KHR);
+                                let (usage_flags, create_flags) =
+                                    get_image_flags(vulkan_format, disjoint, DmatexUsage::Sampling);
+                                let properties = get_drm_image_modifier_info(
+                                    instance,
+                                    physical_device,
+                                    vulkan_format,
+                                    vk::ImageType::TYPE_2D,
+                                    usage_flags,
+                                    create_flags,
+                                    v.drm_format_modifier,
+                                )?;
+                                Some(DmabufModifier {
+                                    modifier: v.drm_format_modifier,
+                                    plane_count: v.drm_format_modifier_plane_count,
+                                    max_extent: Resolution {
+                                        x: properties.max_extent.width,
+                                        y: properties.max_extent.height,
+                                    },
+                                })
+                            })
+                            .collect::<Vec<_>>();
+                        if let Some(implicit_modifier) = resolve_implicit_modifier(
+                            instance,
+                            physical_device,
+                            vulkan_format,
+                            &drm_format_properties,
+                            DmatexUsage::Sampling,
+                            implicit_modifier,
+                        ) {
+                            let properties = implicit_modifier.image_format_properties();
+                            modifiers.push(DmabufModifier {
+                                modifier: DRM_FORMAT_MOD_INVALID,
+                                plane_count: vk_format_ycbcr_info(vulkan_format)
+                                    .map_or(1, |info| info.planes.len() as u32),
+                                max_extent: Resolution {
+                                    x: properties.max_extent.width,
+                                    y: properties.max_extent.height,
+                                },
+                            });
+                        }
+                        (!modifiers.is_empty()).then_some(DmabufFormat {
+                            fourcc: *fourcc as u32,
+                            modifiers,
+                        })
+                    })
+                    .collect();
+                Some(DmabufFormatTable { formats })
+            })
+        }
+    }
+
+    /// true if the table only contains LINEAR and [`DRM_FORMAT_MOD_INVALID`] modifiers because
+    /// the device doesn't support VK_EXT_image_drm_format_modifier, false for an empty table
+    pub fn is_linear_only(&self) -> bool {
+        let mut modifiers = self.formats.iter().flat_map(|v| &v.modifiers).peekable();
+        modifiers.peek().is_some()
+            && modifiers.all(|v| {
+                v.modifier == DRM_FORMAT_MOD_LINEAR || v.modifier == DRM_FORMAT_MOD_INVALID
+            })
+    }
+
+    pub fn get(&self, fourcc: u32) -> Option<&DmabufFormat> {
+        self.formats.iter().find(|v| v.fourcc == fourcc)
+    }
+
+    pub fn get_modifier(&self, fourcc: u32, modifier: u64) -> Option<&DmabufModifier> {
+        self.get(fourcc)?
+            .modifiers
+            .iter()
+            .find(|v| v.modifier == modifier)
+    }
+
+    pub fn supports(&self, fourcc: u32, modifier: u64) -> bool {
+        self.get_modifier(fourcc, modifier).is_some()
+    }
+
+    /// intersects the modifiers a producer can allocate `fourcc` with against the importable ones,
+    /// the result is ordered by preference: tiled/compressed modifiers in the producers order
+    /// first, then LINEAR and [`DRM_FORMAT_MOD_INVALID`] last. An empty result means the producer
+    /// and importer have nothing in common. The table only holds modifiers importable for
+    /// [`DmatexUsage::Sampling`], for other usages check the result with [`DmatexSupportReport`]
+    pub fn negotiate_modifiers(&self, fourcc: u32, producer_modifiers: &[u64]) -> Vec<u64> {
+        let Some(format) = self.get(fourcc) else {
+            return Vec::new();
+        };
+        let mut modifiers = producer_modifiers
+            .iter()
+            .copied()
+            .filter(|modifier| format.modifiers.iter().any(|v| v.modifier == *modifier))
+            .fold(Vec::new(), |mut acc, modifier| {
+                if !acc.contains(&modifier) {
+                    acc.push(modifier);
+                }
+                acc
+            });
+        // stable, so the producers preference is kept within the non linear modifiers
+        modifiers.sort_by_key(|modifier| match *modifier {
+            DRM_FORMAT_MOD_LINEAR => 1,
+            DRM_FORMAT_MOD_INVALID => 2,
+            _ => 0,
+        });
+        modifiers
+    }
+
+    /// the preferred modifier of [`DmabufFormatTable::negotiate_modifiers`]
+    pub fn negotiate_modifier(&self, fourcc: u32, producer_modifiers: &[u64]) -> Option<u64> {
+        self.negotiate_modifiers(fourcc, producer_modifiers)
+            .first()
+            .copied()
+    }
+}
+
+/// explains why a (fourcc, modifier, usage) can or can't be imported on the render device
+#[derive(Clone, Debug)]
+pub struct DmatexSupportReport {
+    pub fourcc: u32,
+    pub modifier: u64,
+    pub usage: DmatexUsage,
+    /// the features the driver advertises for the modifier, empty if it doesn't advertise it
+    pub features: FormatFeatureFlags2,
+    /// number of memory planes a dmabuf with the modifier has
+    pub plane_count: Option<u32>,
+    /// the planes are bound to memory separately, otherwise all planes of a multi-planar modifier
+    /// have to be in the same dmabuf
+    pub disjoint: bool,
+    /// the largest resolution the modifier can be imported with for the usage
+    pub max_extent: Option<Resolution>,
+    /// the errors an import would fail with, empty if the import is supported
+    pub problems: Vec<ImportError>,
+}
+
+impl DmatexSupportReport {
+    /// queries the device, `res` is checked against the max extent if set. [`DRM_FORMAT_MOD_INVALID`]
+    /// is resolved with `implicit_modifier`
+    pub fn new(
+        device: &RenderDevice,
+        fourcc: u32,
+        modifier: u64,
+        usage: DmatexUsage,
+        res: Option<Resolution>,
+        implicit_modifier: ImplicitModifierPolicy,
+    ) -> DmatexSupportReport {
+        let mut report = DmatexSupportReport {
+            fourcc,
+            modifier,
+            usage,
+            features: FormatFeatureFlags2::empty(),
+            plane_count: None,
+            disjoint: false,
+            max_extent: None,
+            problems: Vec::new(),
+        };
+        let Some(vulkan_format) = report.check_format(device) else {
+            return report;
+        };
+        let properties = unsafe {
+            device.wgpu_device().as_hal::<Vulkan, _, _>(|dev| {
+                dev.map(|dev| report.check_modifier(dev, vulkan_format, implicit_modifier))
+            })
+        };
+        let Some(properties) = properties else {
+            report.problems.push(ImportError::NotVulkan);
+            return report;
+        };
+        let Some(properties) = properties else {
+            return report;
+        };
+        let max_extent = Resolution {
+            x: properties.max_extent.width,
+            y: properties.max_extent.height,
+        };
+        report.max_extent = Some(max_extent);
+        if let Some(res) = res
+            && (res.x > max_extent.x || res.y > max_extent.y)
+        {
+            report.problems.push(ImportError::ResolutionTooLarge);
+        }
+        report
+    }
+
+    pub fn is_supported(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// checks the parts that don't depend on the modifier, None if the format can't be imported at
+    /// all
+    fn check_format(&mut self, device: &RenderDevice) -> Option<vk::Format> {
+        let fourcc = match DrmFourcc::try_from(self.fourcc) {
+            Ok(fourcc) => fourcc,
+            Err(err) => {
+                self.problems.push(err.into());
+                return None;
+            }
+        };
+        let Some(vulkan_format) = drm_fourcc_to_vk_format(fourcc) else {
+            self.problems.push(ImportError::VulkanIncompatibleFormat);
+            return None;
+        };
+        // Y′CbCr formats are converted to RGB, so they don't need a wgpu format
+        match vk_format_ycbcr_info(vulkan_format) {
+            Some(_) if self.usage.writes() => {
+                self.problems.push(ImportError::ConversionNotWritable);
+            }
+            Some(_) => {}
+            None => match vulkan_to_wgpu(vulkan_format)
+                .filter(|format| device.features().contains(format.required_features()))
+            {
+                None => self.problems.push(ImportError::WgpuIncompatibleFormat),
+                Some(format)
+                    if self.usage == DmatexUsage::Storage
+                        && !format
+                            .guaranteed_format_features(device.features())
+                            .allowed_usages
+                            .contains(TextureUsages::STORAGE_BINDING) =>
+                {
+                    self.problems.push(ImportError::StorageUnsupported);
+                }
+                Some(_) => {}
+            },
+        }
+        Some(vulkan_format)
+    }
+
+    /// checks the modifier like [`crate::import::import_texture`] does, returns the limits of the
+    /// image if it can be created
+    fn check_modifier(
+        &mut self,
+        dev: &<Vulkan as wgpu::hal::Api>::Device,
+        vulkan_format: vk::Format,
+        implicit_modifier: ImplicitModifierPolicy,
+    ) -> Option<vk::ImageFormatProperties> {
+        let instance = dev.shared_instance().raw_instance();
+        let physical_device = dev.raw_physical_device();
+        let (usage_flags, create_flags) = get_image_flags(vulkan_format, false, self.usage);
+        if !has_drm_format_modifier_support(dev) {
+            if self.modifier != DRM_FORMAT_MOD_LINEAR && self.modifier != DRM_FORMAT_MOD_INVALID {
+                self.problems.push(ImportError::ModifiersUnsupported);
+                return None;
+            }
+            self.plane_count = Some(1);
+            let properties =
+                get_linear_image_info(instance, physical_device, vulkan_format, self.usage);
+            if properties.is_none() {
+                self.problems.push(ImportError::ImageUnsupported {
+                    usage: usage_flags,
+                    flags: create_flags,
+                });
+            }
+            return properties;
+        }
+        let (_format_properties, drm_format_properties) =
+            get_drm_modifiers(instance, physical_device, vulkan_format);
+        if self.modifier == DRM_FORMAT_MOD_INVALID {
+            let implicit_modifier = resolve_implicit_modifier(
+                instance,
+                physical_device,
+                vulkan_format,
+                &drm_format_properties,
+                self.usage,
+                implicit_modifier,
+            );
+            if implicit_modifier.is_none() {
+                self.problems.push(ImportError::ImplicitModifierUnsupported);
+            }
+            return implicit_modifier.map(|v| v.image_format_properties());
+        }
+        let Some(properties) = drm_format_properties
+            .iter()
+            .find(|v| v.drm_format_modifier == self.modifier)
+        else {
+            self.problems.push(ImportError::ModifierInvalid);
+            return None;
+        };
+        self.features = properties.drm_format_modifier_tiling_features;
+        self.plane_count = Some(properties.drm_format_modifier_plane_count);
+        self.disjoint = self.features.contains(FormatFeatureFlags2::DISJOINT_KHR);
+        let missing = self.usage.format_features() & !self.features;
+        if !missing.is_empty() {
+            self.problems.push(ImportError::MissingFeatures(missing));
+        }
+        let (usage_flags, create_flags) = get_image_flags(vulkan_format, self.disjoint, self.usage);
+        let properties = get_drm_image_modifier_info(
+            instance,
+            physical_device,
+            vulkan_format,
+            vk::ImageType::TYPE_2D,
+            usage_flags,
+            create_flags,
+            self.modifier,
+        );
+        if properties.is_none() {
+            self.problems.push(ImportError::ImageUnsupported {
+                usage: usage_flags,
+                flags: create_flags,
+            });
+        }
+        properties
+    }
+}
+
+impl Display for DmatexSupportReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} with modifier {:#018x} for {:?}",
+            FourccName(self.fourcc),
+            self.modifier,
+            self.usage
+        )?;
+        match self.problems.as_slice() {
+            [] => write!(f, " is supported")?,
+            problems => {
+                write!(f, " is unsupported:")?;
+                for problem in problems {
+                    write!(f, " {problem}.")?;
+                }
+            }
+        }
+        if let Some(max_extent) = self.max_extent {
+            write!(f, " max extent {}x{}", max_extent.x, max_extent.y)?;
+        }
+        if let Some(plane_count @ 2..) = self.plane_count {
+            match self.disjoint {
+                true => write!(f, ", {plane_count} disjoint planes")?,
+                false => write!(f, ", {plane_count} planes in the same dmabuf")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// without VK_EXT_image_drm_format_modifier only LINEAR dmabufs can be imported, Y′CbCr formats are
+/// imported plane by plane so every plane format has to support it. [`DRM_FORMAT_MOD_INVALID`] is
+/// imported as LINEAR
+fn get_linear_only_format(
+    instance: &ash::Instance,
+    physical_device: vk::PhysicalDevice,
+    fourcc: u32,
+    vulkan_format: vk::Format,
+) -> Option<DmabufFormat> {
+    let ycbcr_info = vk_format_ycbcr_info(vulkan_format);
+    let plane_formats = match &ycbcr_info {
+        Some(info) => info.planes,
+        None => slice::from_ref(&vulkan_format),
+    };
+    let max_extent = plane_formats.iter().try_fold(
+        Resolution {
+            x: u32::MAX,
+            y: u32::MAX,
+        },
+        |res, format| {
+            let properties =
+                get_linear_image_info(instance, physical_device, *format, DmatexUsage::Sampling)?;
+            Some(Resolution {
+                x: res.x.min(properties.max_extent.width),
+                y: res.y.min(properties.max_extent.height),
+            })
+        },
+    )?;
+    Some(DmabufFormat {
+        fourcc,
+        modifiers: [DRM_FORMAT_MOD_LINEAR, DRM_FORMAT_MOD_INVALID]
+            .map(|modifier| DmabufModifier {
+                modifier,
+                plane_count: plane_formats.len() as u32,
+                max_extent,
+            })
+            .into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TILED_A: u64 = 0x0100_0000_0000_0001;
+    const TILED_B: u64 = 0x0100_0000_0000_0002;
+    const TILED_C: u64 = 0x0100_0000_0000_0003;
+
+    fn table() -> DmabufFormatTable {
+        let modifier = |modifier| DmabufModifier {
+            modifier,
+            plane_count: 1,
+            max_extent: Resolution { x: 4096, y: 4096 },
+        };
+        DmabufFormatTable {
+            formats: vec![DmabufFormat {
+                fourcc: DrmFourcc::Argb8888 as u32,
+                modifiers: vec![
+                    modifier(DRM_FORMAT_MOD_INVALID),
+                    modifier(DRM_FORMAT_MOD_LINEAR),
+                    modifier(TILED_A),
+                    modifier(TILED_B),
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn negotiate_modifiers_orders_by_preference() {
+        let modifiers = table().negotiate_modifiers(
+            DrmFourcc::Argb8888 as u32,
+            &[
+                DRM_FORMAT_MOD_INVALID,
+                DRM_FORMAT_MOD_LINEAR,
+                TILED_B,
+                TILED_A,
+            ],
+        );
+        assert_eq!(
+            modifiers,
+            [
+                TILED_B,
+                TILED_A,
+                DRM_FORMAT_MOD_LINEAR,
+                DRM_FORMAT_MOD_INVALID
+            ]
+        );
+    }
+
+    #[test]
+    fn is_linear_only() {
+        assert!(!DmabufFormatTable::default().is_linear_only());
+        assert!(!table().is_linear_only());
+        let mut table = table();
+        table.formats[0]
+            .modifiers
+            .retain(|v| v.modifier != TILED_A && v.modifier != TILED_B);
+        assert!(table.is_linear_only());
+    }
+
+    #[test]
+    fn negotiate_modifiers_dedups() {
+        let modifiers = table().negotiate_modifiers(
+            DrmFourcc::Argb8888 as u32,
+            &[
+                TILED_A,
+                DRM_FORMAT_MOD_LINEAR,
+                TILED_A,
+                DRM_FORMAT_MOD_LINEAR,
+            ],
+        );
+        assert_eq!(modifiers, [TILED_A, DRM_FORMAT_MOD_LINEAR]);
+    }
+
+    #[test]
+    fn negotiate_modifiers_empty_intersection() {
+        let table = table();
+        assert!(
+            table
+                .negotiate_modifiers(DrmFourcc::Argb8888 as u32, &[TILED_C])
+                .is_empty()
+        );
+        assert!(
+            table
+                .negotiate_modifiers(DrmFourcc::Nv12 as u32, &[DRM_FORMAT_MOD_LINEAR])
+                .is_empty()
+        );
+        assert_eq!(
+            table.negotiate_modifier(DrmFourcc::Argb8888 as u32, &[TILED_C]),
+            None
+        );
+    }
+}
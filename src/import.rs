@@ -1,22 +1,28 @@
 #![warn(clippy::unwrap_used, clippy::expect_used)]
 use std::{
-    fmt::Debug,
-    os::fd::{IntoRawFd as _, OwnedFd},
-    sync::{Arc, Mutex},
+    fmt::{Debug, Display},
+    io, iter,
+    os::fd::{AsFd, AsRawFd as _, FromRawFd as _, IntoRawFd as _, OwnedFd},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
 };
 
 use ash::vk::{
-    self, CommandBufferBeginInfo, FormatFeatureFlags2, ImagePlaneMemoryRequirementsInfo,
-    MemoryDedicatedRequirements, MemoryRequirements2, SubresourceLayout,
+    self, CommandBufferBeginInfo, FormatFeatureFlags2, ImageFormatProperties,
+    ImagePlaneMemoryRequirementsInfo, MemoryDedicatedRequirements, MemoryRequirements2,
+    SubresourceLayout,
 };
 use bevy::{
-    app::Plugin,
-    asset::{Assets, Handle, RenderAssetUsages},
+    app::{Last, Plugin, PreUpdate},
+    asset::{Asset, AssetId, Assets, Handle, RenderAssetUsages},
     ecs::{
+        event::{Event, EventWriter},
         resource::Resource,
         schedule::{IntoScheduleConfigs as _, SystemSet},
         system::{Res, ResMut},
-        world::World,
+        world::{FromWorld, Mut, World},
     },
     image::Image,
     pbr::{PreparedMaterial, StandardMaterial},
@@ -26,7 +32,7 @@ use bevy::{
         extract_resource::{ExtractResource, ExtractResourcePlugin},
         render_asset::{RenderAssets, prepare_assets},
         render_resource::{Texture, TextureView},
-        renderer::RenderDevice,
+        renderer::{RenderDevice, RenderQueue},
         texture::GpuImage,
     },
     utils::default,
@@ -40,10 +46,19 @@ use wgpu::{
 };
 
 use crate::{
-    dmatex::Dmatex,
+    convert::{ConvertParams, ConvertedTexture, DmatexConverter, YcbcrParams},
+    cpu_upload::{CpuUploadedTexture, cpu_upload_format},
+    dmatex::{Dmatex, DmatexPlane, DmatexTimelinePoint, DmatexTransform, Resolution},
     format_mapping::{
-        drm_fourcc_to_vk_format, get_drm_image_modifier_info, get_drm_modifiers, vk_format_to_srgb,
+        YcbcrFormatInfo, drm_fourcc_to_vk_format, get_drm_image_modifier_info, get_drm_modifiers,
+        get_external_image_info, vk_format_to_srgb, vk_format_ycbcr_info,
+    },
+    format_table::{
+        DRM_FORMAT_MOD_INVALID, DRM_FORMAT_MOD_LINEAR, DmabufFormatTable, DmatexSupportReport,
     },
+    import_cache::{DmabufId, ImportCache, ImportCacheKey},
+    sync_file::{self, DMA_BUF_SYNC_READ, DMA_BUF_SYNC_WRITE},
+    validate::validate_dmatex,
     wgpu_init::vulkan_to_wgpu,
 };
 
@@ -51,9 +66,30 @@ pub struct DmabufImportPlugin;
 
 impl Plugin for DmabufImportPlugin {
     fn build(&self, app: &mut bevy::app::App) {
-        let handles = ImportedDmatexs(default());
+        app.init_resource::<DmabufImportSettings>();
+        let settings = app.world().resource::<DmabufImportSettings>();
+        let handles = ImportedDmatexs {
+            dmatexs: default(),
+            cache: default(),
+            releases: default(),
+            import_events: default(),
+            removed: default(),
+            replaced: default(),
+            cpu_upload_fallback: settings.cpu_upload_fallback,
+        };
         app.insert_resource(handles.clone());
+        app.add_event::<DmatexImported>();
+        app.add_event::<DmatexImportFailed>();
+        app.add_systems(PreUpdate, send_dmatex_import_events);
+        app.add_systems(
+            Last,
+            (
+                refresh_dmatex_materials::<StandardMaterial>.in_set(RefreshDmatexMaterials),
+                clear_replaced_dmatexs.after(RefreshDmatexMaterials),
+            ),
+        );
         app.add_plugins(ExtractResourcePlugin::<ImportedDmatexs>::default());
+        app.add_plugins(ExtractResourcePlugin::<DmabufImportSettings>::default());
         if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
             render_app.configure_sets(
                 Render,
@@ -65,6 +101,9 @@ impl Plugin for DmabufImportPlugin {
                     DmatexRenderSystemSet::AcquireDmatexs
                         .in_set(RenderSet::PrepareAssets)
                         .after(DmatexRenderSystemSet::InsertIntoGpuImages),
+                    DmatexRenderSystemSet::ConvertDmatexs
+                        .in_set(RenderSet::PrepareAssets)
+                        .after(DmatexRenderSystemSet::AcquireDmatexs),
                     DmatexRenderSystemSet::ReleaseDmatexs.in_set(RenderSet::Cleanup),
                 ),
             );
@@ -76,34 +115,369 @@ impl Plugin for DmabufImportPlugin {
                 Render,
                 (
                     acquire_dmatex_images.in_set(DmatexRenderSystemSet::AcquireDmatexs),
+                    upload_cpu_dmatex_images.in_set(DmatexRenderSystemSet::AcquireDmatexs),
+                    convert_dmatex_images.in_set(DmatexRenderSystemSet::ConvertDmatexs),
                     release_dmatex_images.in_set(DmatexRenderSystemSet::ReleaseDmatexs),
+                    evict_unused_dmatex_imports.in_set(DmatexRenderSystemSet::ReleaseDmatexs),
                 ),
             );
         } else {
             warn!("unable to init dmabuf importing!");
         }
     }
+
+    fn finish(&self, app: &mut bevy::app::App) {
+        let format_table = app
+            .world()
+            .get_resource::<RenderDevice>()
+            .and_then(|device| {
+                let settings = app.world().resource::<DmabufImportSettings>();
+                DmabufFormatTable::new(device, settings.implicit_modifier)
+            });
+        match format_table {
+            Some(format_table) => {
+                debug!("dmabuf format table: {format_table:?}");
+                app.insert_resource(format_table.clone());
+                if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+                    render_app.insert_resource(format_table);
+                }
+            }
+            None => warn!("unable to query importable dmabuf formats!"),
+        }
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.init_resource::<DmatexConverter>();
+            render_app.init_resource::<BarrierCommandBuffers>();
+        }
+    }
 }
 
+/// runs [`refresh_dmatex_materials`] in [`Last`], add it for custom materials using dmatexs
+#[derive(SystemSet, Hash, Debug, Clone, PartialEq, Eq, Copy)]
+pub struct RefreshDmatexMaterials;
+
 #[derive(SystemSet, Hash, Debug, Clone, PartialEq, Eq, Copy)]
 pub enum DmatexRenderSystemSet {
     InsertIntoGpuImages,
     AcquireDmatexs,
+    ConvertDmatexs,
     ReleaseDmatexs,
 }
 
+/// insert before adding [`DmabufImportPlugin`] to change the defaults
+#[derive(Resource, Clone, Debug, ExtractResource)]
+pub struct DmabufImportSettings {
+    /// if a LINEAR dmatex can't be imported by the gpu, map it and upload its contents from the
+    /// cpu every frame instead, see [`CpuUploadedTexture`]. Explicit sync acquire points are
+    /// ignored by the fallback
+    pub cpu_upload_fallback: bool,
+    /// how many frames the import of a dmabuf is kept around after its last use, so producers
+    /// cycling through a pool of buffers don't cause a new import every frame. 0 disables the
+    /// cache. Cached imports are identified by the inode of the dmabuf and its layout.
+    /// A cached import keeps its dmabufs alive, so once the producer destroys a buffer
+    /// [`ImportedDmatexs::evict_cached_import`] has to be called or the memory is only freed after
+    /// these frames
+    pub import_cache_frames: u32,
+    /// the queue family dmatexs are acquired from and released to
+    pub external_queue_family: ExternalQueueFamily,
+    /// wait for the implicit fences of the dmabufs before using them and attach a fence that
+    /// signals once we're done back to them, for producers that rely on implicit sync like GL.
+    /// Requires VK_KHR_external_semaphore_fd and linux 6.0, dmatexs with [`Dmatex::sync`] set
+    /// always use explicit sync
+    pub implicit_sync: bool,
+    /// how dmatexs with the implicit modifier [`DRM_FORMAT_MOD_INVALID`] are imported
+    pub implicit_modifier: ImplicitModifierPolicy,
+}
+
+/// the queue family the producer owns dmatexs on while we don't use them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExternalQueueFamily {
+    /// VK_QUEUE_FAMILY_EXTERNAL, for producers using vulkan with the same driver and device
+    #[default]
+    External,
+    /// VK_QUEUE_FAMILY_FOREIGN_EXT, for any other producer, e.g. GL, video decoders or another
+    /// gpu. Requires VK_EXT_queue_family_foreign to be enabled, without it
+    /// VK_QUEUE_FAMILY_EXTERNAL is used
+    Foreign,
+}
+
+/// the layout dmatexs with the implicit modifier [`DRM_FORMAT_MOD_INVALID`] are assumed to have,
+/// falls back to the other one if the driver can't import the format with the preferred one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImplicitModifierPolicy {
+    /// import as LINEAR with the offsets and strides of the planes, for producers that allocate
+    /// LINEAR buffers without announcing a modifier
+    #[default]
+    PreferLinear,
+    /// import without a drm modifier and let the driver derive the layout, for producers on the
+    /// same driver that allocate tiled buffers without modifiers, like older GL and Vulkan ones.
+    /// Multi-planar formats are always imported as LINEAR
+    PreferDriverTiling,
+}
+
+impl Default for DmabufImportSettings {
+    fn default() -> Self {
+        Self {
+            cpu_upload_fallback: false,
+            import_cache_frames: 16,
+            external_queue_family: ExternalQueueFamily::External,
+            implicit_sync: true,
+            implicit_modifier: ImplicitModifierPolicy::PreferLinear,
+        }
+    }
+}
+
 #[derive(Resource, Clone, ExtractResource)]
-pub struct ImportedDmatexs(Arc<Mutex<HashMap<Handle<Image>, DmaImage>>>);
+pub struct ImportedDmatexs {
+    dmatexs: Arc<Mutex<HashMap<Handle<Image>, DmaImage>>>,
+    cache: Arc<Mutex<ImportCache>>,
+    /// release points and callbacks of dropped dmatexs, signaled and run once the gpu finished
+    /// the frames that used them
+    releases: Arc<Mutex<Vec<PendingRelease>>>,
+    /// results of the imports in the render world, sent as events in the main world
+    import_events: Arc<Mutex<Vec<ImportEvent>>>,
+    /// dmatexs removed with [`ImportedDmatexs::remove`] or replaced with
+    /// [`ImportedDmatexs::replace`], released by the next release submission
+    removed: Arc<Mutex<Vec<DmaImage>>>,
+    /// images whose dmatex was replaced this frame, the materials using them have to rebuild their
+    /// bind groups
+    replaced: Arc<Mutex<Vec<AssetId<Image>>>>,
+    /// [`DmabufImportSettings::cpu_upload_fallback`] when the plugin was added
+    cpu_upload_fallback: bool,
+}
+
+enum ImportEvent {
+    Imported(Handle<Image>),
+    Failed(Handle<Image>, DmatexImportError),
+}
+
+/// sent once a dmatex set through [`ImportedDmatexs`] was imported, from then on the image shows
+/// the dmatex. `handle` is a weak handle
+#[derive(Event, Clone, Debug)]
+pub struct DmatexImported {
+    pub handle: Handle<Image>,
+}
+
+/// sent if a dmatex set through [`ImportedDmatexs`] couldn't be imported, the image stays
+/// uninitialized. `handle` is a weak handle
+#[derive(Event, Clone, Debug)]
+pub struct DmatexImportFailed {
+    pub handle: Handle<Image>,
+    pub error: DmatexImportError,
+}
+
+/// a dropped dmatex, the gpu might still be using it in the current frame
+struct PendingRelease {
+    /// explicit sync release point
+    point: Option<DmatexTimelinePoint>,
+    on_release: DropCallback,
+}
 
 #[derive(Debug)]
 enum DmaImage {
     UnImported(Dmatex, DropCallback, DmatexUsage),
-    Imported(ImportedTexture),
+    Imported(Box<ImportedTexture>),
+    CpuUploaded(Box<CpuUploadedTexture>),
+    Failed(DmatexImportError),
 }
 
-#[derive(Clone, Copy, Debug)]
+impl DmaImage {
+    fn output_size_and_format(
+        &self,
+        cpu_upload_fallback: bool,
+    ) -> Option<(wgpu::Extent3d, wgpu::TextureFormat)> {
+        let texture = match self {
+            DmaImage::UnImported(buf, _, usage) => {
+                let image = get_image(buf, *usage, cpu_upload_fallback).ok()?;
+                return Some((
+                    image.texture_descriptor.size,
+                    image.texture_descriptor.format,
+                ));
+            }
+            DmaImage::Imported(tex) => tex.output_texture(),
+            DmaImage::CpuUploaded(tex) => tex.texture(),
+            DmaImage::Failed(_) => return None,
+        };
+        Some((texture.size(), texture.format()))
+    }
+}
+
+/// where the dmatex behind a handle is in the import process
+#[derive(Clone, Debug)]
+pub enum DmatexStatus {
+    /// set, but not imported by the render world yet
+    Pending,
+    Imported,
+    /// imported through the cpu upload fallback
+    CpuUploaded,
+    /// the import failed, the image stays uninitialized until the dmatex is replaced
+    Failed(DmatexImportError),
+    /// the handle was never set or its image was dropped
+    Removed,
+}
+
+/// the metadata of a dmatex without its fds, describes the dmatex behind a handle and is attached
+/// to import errors. The size and format of the texture are the ones of the image
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DmatexInfo {
+    /// drm fourcc
+    pub format: u32,
+    /// resolution of the dmabuf before the transform is applied
+    pub res: Resolution,
+    pub planes: Vec<DmatexPlaneInfo>,
+    pub transform: DmatexTransform,
+    pub srgb: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DmatexPlaneInfo {
+    pub modifier: u64,
+    pub offset: u32,
+    pub stride: i32,
+}
+
+impl DmatexInfo {
+    pub fn new(buf: &Dmatex) -> DmatexInfo {
+        DmatexInfo {
+            format: buf.format,
+            res: buf.res,
+            planes: buf
+                .planes
+                .iter()
+                .map(|plane| DmatexPlaneInfo {
+                    modifier: plane.modifier,
+                    offset: plane.offset,
+                    stride: plane.stride,
+                })
+                .collect(),
+            transform: buf.transform,
+            srgb: buf.srgb,
+        }
+    }
+    /// drm modifier of the first plane
+    pub fn modifier(&self) -> u64 {
+        self.planes
+            .first()
+            .map_or(DRM_FORMAT_MOD_INVALID, |plane| plane.modifier)
+    }
+    pub fn plane_count(&self) -> usize {
+        self.planes.len()
+    }
+}
+
+/// the name of a drm fourcc, or its value if it's unknown
+pub(crate) struct FourccName(pub(crate) u32);
+
+impl Display for FourccName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match DrmFourcc::try_from(self.0) {
+            Ok(fourcc) => write!(f, "{fourcc:?}"),
+            Err(_) => write!(f, "fourcc {:#010x}", self.0),
+        }
+    }
+}
+
+impl Display for DmatexInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}x{}",
+            FourccName(self.format),
+            self.res.x,
+            self.res.y
+        )?;
+        for (i, plane) in self.planes.iter().enumerate() {
+            write!(
+                f,
+                ", plane {i}: modifier {:#018x} offset {} stride {}",
+                plane.modifier, plane.offset, plane.stride
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DmatexUsage {
     Sampling,
+    /// rendered into by a camera with `RenderTarget::Image`, the texture must not be used in any
+    /// other way. Requires a RGB dmatex without a transform or color conversion
+    RenderTarget,
+    /// read and written by compute shaders as a storage texture, the texture must not be used in
+    /// any other way. Requires a RGB dmatex without a transform or color conversion, in a format
+    /// and modifier the gpu supports storage images for
+    Storage,
+}
+
+impl DmatexUsage {
+    /// the layout wgpu keeps the texture in for this usage, and the stages and accesses that use
+    /// it while it's owned by our queue
+    fn barrier_info(self) -> (vk::ImageLayout, vk::PipelineStageFlags, vk::AccessFlags) {
+        match self {
+            DmatexUsage::Sampling => (
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::AccessFlags::SHADER_READ,
+            ),
+            DmatexUsage::RenderTarget => (
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            ),
+            DmatexUsage::Storage => (
+                vk::ImageLayout::GENERAL,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+            ),
+        }
+    }
+    /// if we write to the dmabuf, the writes have to end up in the dmabuf itself so the texture
+    /// can't be converted or uploaded from the cpu
+    pub fn writes(self) -> bool {
+        match self {
+            DmatexUsage::Sampling => false,
+            DmatexUsage::RenderTarget | DmatexUsage::Storage => true,
+        }
+    }
+    /// the only wgpu usage the imported texture gets, matches [`DmatexUsage::image_usage`]
+    fn texture_usage(self) -> TextureUsages {
+        match self {
+            DmatexUsage::Sampling => TextureUsages::TEXTURE_BINDING,
+            DmatexUsage::RenderTarget => TextureUsages::RENDER_ATTACHMENT,
+            DmatexUsage::Storage => TextureUsages::STORAGE_BINDING,
+        }
+    }
+    /// the only vulkan usage the imported image gets, so modifiers that only support some usages
+    /// can still be imported
+    fn image_usage(self) -> vk::ImageUsageFlags {
+        match self {
+            DmatexUsage::Sampling => vk::ImageUsageFlags::SAMPLED,
+            DmatexUsage::RenderTarget => vk::ImageUsageFlags::COLOR_ATTACHMENT,
+            DmatexUsage::Storage => vk::ImageUsageFlags::STORAGE,
+        }
+    }
+    /// the usage wgpu-hal creates views of the image with
+    fn texture_uses(self) -> TextureUses {
+        match self {
+            DmatexUsage::Sampling => TextureUses::RESOURCE,
+            DmatexUsage::RenderTarget => TextureUses::COLOR_TARGET,
+            DmatexUsage::Storage => TextureUses::STORAGE_READ_WRITE,
+        }
+    }
+    /// the features a modifier has to support for the usage
+    pub(crate) fn format_features(self) -> FormatFeatureFlags2 {
+        match self {
+            DmatexUsage::Sampling => FormatFeatureFlags2::SAMPLED_IMAGE,
+            DmatexUsage::RenderTarget => FormatFeatureFlags2::COLOR_ATTACHMENT,
+            DmatexUsage::Storage => FormatFeatureFlags2::STORAGE_IMAGE,
+        }
+    }
+    /// how our accesses are fenced for implicitly synced producers
+    fn sync_file_flags(self) -> u32 {
+        match self.writes() {
+            true => DMA_BUF_SYNC_WRITE,
+            false => DMA_BUF_SYNC_READ,
+        }
+    }
 }
 
 pub struct DropCallback(pub Option<Box<dyn FnOnce() + 'static + Send + Sync>>);
@@ -127,15 +501,108 @@ impl ImportedDmatexs {
         buf: Dmatex,
         usage: DmatexUsage,
         on_drop: Option<Box<dyn FnOnce() + 'static + Send + Sync>>,
-    ) -> Result<Handle<Image>, ImportError> {
-        let handle = get_handle(images, &buf)?;
+    ) -> Result<Handle<Image>, DmatexImportError> {
+        let (image, on_drop) = self.prepare(&buf, usage, on_drop)?;
+        let handle = images.add(image);
         #[expect(clippy::unwrap_used)]
-        self.0.lock().unwrap().insert(
+        self.dmatexs.lock().unwrap().insert(
             handle.clone_weak(),
-            DmaImage::UnImported(buf, DropCallback(on_drop), usage),
+            DmaImage::UnImported(buf, on_drop, usage),
         );
         Ok(handle)
     }
+    /// replaces the dmatex behind an existing `handle`, so materials using it show the new dmatex
+    /// without being touched, their bind groups are rebuilt by [`refresh_dmatex_materials`]. The
+    /// previous dmatex is released and its drop callback called once the gpu finished the last
+    /// frame that used it. The import of the previous dmatex stays cached, so producers can cycle
+    /// through a pool of buffers behind the same handle
+    pub fn replace(
+        &self,
+        images: &mut Assets<Image>,
+        handle: &Handle<Image>,
+        buf: Dmatex,
+        usage: DmatexUsage,
+        on_drop: Option<Box<dyn FnOnce() + 'static + Send + Sync>>,
+    ) -> Result<(), DmatexImportError> {
+        let (image, on_drop) = self.prepare(&buf, usage, on_drop)?;
+        #[expect(clippy::unwrap_used)]
+        let mut dmatexs = self.dmatexs.lock().unwrap();
+        let unchanged = dmatexs
+            .get(handle)
+            .and_then(|previous| previous.output_size_and_format(self.cpu_upload_fallback))
+            .is_some_and(|(size, format)| {
+                size == image.texture_descriptor.size && format == image.texture_descriptor.format
+            });
+        // only recreate the GpuImage if the placeholder doesn't match anymore
+        if !unchanged {
+            images.insert(handle.id(), image);
+        }
+        let previous = dmatexs.insert(
+            handle.clone_weak(),
+            DmaImage::UnImported(buf, on_drop, usage),
+        );
+        drop(dmatexs);
+        // like a removed dmatex, the previous one has to be released to the external queue
+        if let Some(previous) = previous {
+            #[expect(clippy::unwrap_used)]
+            self.removed.lock().unwrap().push(previous);
+        }
+        #[expect(clippy::unwrap_used)]
+        self.replaced.lock().unwrap().push(handle.id());
+        Ok(())
+    }
+    /// validates a newly set dmatex, returns its placeholder image and wrapped drop callback
+    fn prepare(
+        &self,
+        buf: &Dmatex,
+        usage: DmatexUsage,
+        on_drop: Option<Box<dyn FnOnce() + 'static + Send + Sync>>,
+    ) -> Result<(Image, DropCallback), DmatexImportError> {
+        validate_dmatex(buf)
+            .and_then(|()| {
+                let image = get_image(buf, usage, self.cpu_upload_fallback)?;
+                Ok((image, self.release_on_drop(buf, on_drop)?))
+            })
+            .map_err(|err| DmatexImportError::new(err, DmatexInfo::new(buf)))
+    }
+    /// wraps the drop callback so once the dmatex is dropped, the callback only runs and the
+    /// explicit sync release point is only signaled once the gpu finished the last frame that
+    /// used the dmatex
+    fn release_on_drop(
+        &self,
+        buf: &Dmatex,
+        on_drop: Option<Box<dyn FnOnce() + 'static + Send + Sync>>,
+    ) -> Result<DropCallback, ImportError> {
+        let point = buf
+            .sync
+            .0
+            .as_ref()
+            .map(|sync| sync.release.try_clone())
+            .transpose()
+            .map_err(|err| ImportError::FdDupFailed(err.raw_os_error().unwrap_or(libc::EIO)))?;
+        if point.is_none() && on_drop.is_none() {
+            return Ok(DropCallback(None));
+        }
+        let releases = self.releases.clone();
+        Ok(DropCallback(Some(Box::new(move || {
+            let release = PendingRelease {
+                point,
+                on_release: DropCallback(on_drop),
+            };
+            match releases.lock() {
+                Ok(mut releases) => releases.push(release),
+                Err(err) => error!("Unable to lock pending dmatex releases: {err}"),
+            }
+        }))))
+    }
+    /// drops the cached import of the dmabuf, call this once the producer destroys the buffer so
+    /// the memory is freed before the cache would evict it
+    pub fn evict_cached_import(&self, dmabuf_fd: impl AsFd) -> io::Result<()> {
+        let id = DmabufId::new(dmabuf_fd)?;
+        #[expect(clippy::unwrap_used)]
+        self.cache.lock().unwrap().evict_dmabuf(id);
+        Ok(())
+    }
     pub fn insert_imported_dmatex(
         &self,
         images: &mut Assets<Image>,
@@ -152,23 +619,215 @@ impl ImportedDmatexs {
 
         let _span = debug_span!("inserting image handle").entered();
         #[expect(clippy::unwrap_used)]
-        self.0
+        self.dmatexs
             .lock()
             .unwrap()
-            .insert(handle.clone_weak(), DmaImage::Imported(tex));
+            .insert(handle.clone_weak(), DmaImage::Imported(Box::new(tex)));
         handle
     }
+    /// removes the image and the dmatex behind `handle`, the dmatex is released to the external
+    /// queue by the next frame, its texture is destroyed and its drop callback called once the
+    /// gpu is done with it. Cached imports of its dmabufs are evicted
+    pub fn remove(&self, images: &mut Assets<Image>, handle: &Handle<Image>) {
+        images.remove(handle);
+        #[expect(clippy::unwrap_used)]
+        let Some(removed) = self.dmatexs.lock().unwrap().remove(handle) else {
+            return;
+        };
+        self.evict_cached_imports(&removed);
+        #[expect(clippy::unwrap_used)]
+        self.removed.lock().unwrap().push(removed);
+    }
+    /// drops the cached imports of the dmabufs of a dmatex that is no longer used
+    fn evict_cached_imports(&self, dmatex: &DmaImage) {
+        let DmaImage::Imported(tex) = dmatex else {
+            return;
+        };
+        #[expect(clippy::unwrap_used)]
+        let mut cache = self.cache.lock().unwrap();
+        for dmabuf in tex.dmabufs.iter() {
+            match DmabufId::new(dmabuf) {
+                Ok(id) => cache.evict_dmabuf(id),
+                Err(err) => warn!("unable to identify dmabuf for the import cache: {err}"),
+            }
+        }
+    }
+    pub fn status(&self, handle: &Handle<Image>) -> DmatexStatus {
+        let Ok(dmatexs) = self
+            .dmatexs
+            .lock()
+            .inspect_err(|e| error!("Unable to lock dmatexs: {e}"))
+        else {
+            return DmatexStatus::Removed;
+        };
+        match dmatexs.get(handle) {
+            Some(DmaImage::UnImported(_, _, _)) => DmatexStatus::Pending,
+            Some(DmaImage::Imported(_)) => DmatexStatus::Imported,
+            Some(DmaImage::CpuUploaded(_)) => DmatexStatus::CpuUploaded,
+            Some(DmaImage::Failed(err)) => DmatexStatus::Failed(err.clone()),
+            None => DmatexStatus::Removed,
+        }
+    }
+    /// None if the handle isn't set or the texture was inserted with
+    /// [`Self::insert_imported_dmatex`]
+    pub fn info(&self, handle: &Handle<Image>) -> Option<DmatexInfo> {
+        let dmatexs = self
+            .dmatexs
+            .lock()
+            .inspect_err(|e| error!("Unable to lock dmatexs: {e}"))
+            .ok()?;
+        match dmatexs.get(handle)? {
+            DmaImage::UnImported(buf, _, _) => Some(DmatexInfo::new(buf)),
+            DmaImage::Imported(tex) => tex.info.clone(),
+            DmaImage::CpuUploaded(tex) => Some(tex.info()),
+            DmaImage::Failed(err) => Some(err.dmatex.clone()),
+        }
+    }
+}
+
+/// marks the `M` assets depending on an image whose dmatex was replaced this frame as changed, so
+/// they are prepared again and their bind groups use the new texture instead of the previous one.
+/// Added for [`StandardMaterial`] by [`DmabufImportPlugin`], add it to [`Last`] in
+/// [`RefreshDmatexMaterials`] for other materials
+pub fn refresh_dmatex_materials<M: Asset>(
+    imported: Res<ImportedDmatexs>,
+    materials: Option<ResMut<Assets<M>>>,
+) {
+    let Some(mut materials) = materials else {
+        return;
+    };
+    let Ok(replaced) = imported
+        .replaced
+        .lock()
+        .inspect_err(|e| error!("Unable to lock replaced dmatexs: {e}"))
+    else {
+        return;
+    };
+    if replaced.is_empty() {
+        return;
+    }
+    let changed = materials
+        .iter()
+        .filter(|(_, material)| {
+            let mut uses_replaced = false;
+            material.visit_dependencies(&mut |id| {
+                uses_replaced |= replaced.iter().any(|replaced| id == replaced.untyped());
+            });
+            uses_replaced
+        })
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    for id in changed {
+        materials.get_mut(id);
+    }
+}
+
+fn clear_replaced_dmatexs(imported: Res<ImportedDmatexs>) {
+    match imported.replaced.lock() {
+        Ok(mut replaced) => replaced.clear(),
+        Err(e) => error!("Unable to lock replaced dmatexs: {e}"),
+    }
+}
+
+fn send_dmatex_import_events(
+    imported: Res<ImportedDmatexs>,
+    mut imported_events: EventWriter<DmatexImported>,
+    mut failed_events: EventWriter<DmatexImportFailed>,
+) {
+    let Ok(mut events) = imported
+        .import_events
+        .lock()
+        .inspect_err(|e| error!("Unable to lock dmatex import events: {e}"))
+    else {
+        return;
+    };
+    for event in events.drain(..) {
+        match event {
+            ImportEvent::Imported(handle) => {
+                imported_events.write(DmatexImported { handle });
+            }
+            ImportEvent::Failed(handle, error) => {
+                failed_events.write(DmatexImportFailed { handle, error });
+            }
+        }
+    }
 }
 
 fn acquire_dmatex_images(world: &mut World) {
-    let device = world.resource::<RenderDevice>();
-    let dmatexs = world.resource::<ImportedDmatexs>();
-    memory_barrier(device, dmatexs, ImageQueueTransfer::Acquire);
+    world.resource_scope(|world, mut command_buffers: Mut<BarrierCommandBuffers>| {
+        let device = world.resource::<RenderDevice>();
+        let dmatexs = world.resource::<ImportedDmatexs>();
+        let settings = world.resource::<DmabufImportSettings>();
+        memory_barrier(
+            device,
+            dmatexs,
+            settings,
+            &mut command_buffers,
+            ImageQueueTransfer::Acquire,
+        );
+    });
 }
 fn release_dmatex_images(world: &mut World) {
-    let device = world.resource::<RenderDevice>();
-    let dmatexs = world.resource::<ImportedDmatexs>();
-    memory_barrier(device, dmatexs, ImageQueueTransfer::Release);
+    world.resource_scope(|world, mut command_buffers: Mut<BarrierCommandBuffers>| {
+        let device = world.resource::<RenderDevice>();
+        let dmatexs = world.resource::<ImportedDmatexs>();
+        let settings = world.resource::<DmabufImportSettings>();
+        memory_barrier(
+            device,
+            dmatexs,
+            settings,
+            &mut command_buffers,
+            ImageQueueTransfer::Release,
+        );
+    });
+}
+
+fn upload_cpu_dmatex_images(queue: Res<RenderQueue>, imported: Res<ImportedDmatexs>) {
+    let Ok(texes) = imported
+        .dmatexs
+        .lock()
+        .inspect_err(|e| error!("Unable to lock dmatexs: {e}"))
+    else {
+        return;
+    };
+    for tex in texes.values().filter_map(|v| match v {
+        DmaImage::CpuUploaded(tex) => Some(tex),
+        _ => None,
+    }) {
+        if let Err(err) = tex.upload(&queue) {
+            error!("failed to upload dmatex: {err}");
+        }
+    }
+}
+
+fn convert_dmatex_images(
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+    converter: Res<DmatexConverter>,
+    imported: Res<ImportedDmatexs>,
+) {
+    let Ok(texes) = imported
+        .dmatexs
+        .lock()
+        .inspect_err(|e| error!("Unable to lock dmatexs: {e}"))
+    else {
+        return;
+    };
+    let mut encoder = None;
+    for converted in texes.values().filter_map(|v| match v {
+        DmaImage::Imported(tex) => tex.converted.as_ref(),
+        DmaImage::UnImported(_, _, _) | DmaImage::CpuUploaded(_) | DmaImage::Failed(_) => None,
+    }) {
+        let encoder = encoder.get_or_insert_with(|| {
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("dmatex convert encoder"),
+            })
+        });
+        converter.encode(encoder, converted);
+    }
+    if let Some(encoder) = encoder {
+        queue.submit([encoder.finish()]);
+    }
 }
 
 enum ImageQueueTransfer {
@@ -176,9 +835,440 @@ enum ImageQueueTransfer {
     Release,
 }
 
+/// command buffers for the queue ownership barriers, a command buffer is only reused once the gpu
+/// signaled the timeline semaphore value of its last submission, so recording never waits on the
+/// gpu
+#[derive(Resource)]
+struct BarrierCommandBuffers {
+    /// keeps the vulkan device alive until the command pool is destroyed
+    device: RenderDevice,
+    command_pool: vk::CommandPool,
+    timeline_semaphore: vk::Semaphore,
+    next_value: u64,
+    submissions: Vec<BarrierSubmission>,
+    /// None if VK_KHR_external_semaphore_fd isn't enabled
+    semaphore_fd: Option<ash::khr::external_semaphore_fd::Device>,
+    /// false if VK_EXT_queue_family_foreign isn't enabled
+    queue_family_foreign: bool,
+    /// set once the fallback from [`ExternalQueueFamily::Foreign`] was logged
+    queue_family_foreign_warned: bool,
+    /// whether the driver implements OPAQUE_FD semaphores as drm syncobjs, see
+    /// [`driver_uses_drm_syncobjs`]
+    syncobj_semaphores: bool,
+    /// binary semaphores the implicit fences of the dmabufs get imported into
+    free_wait_semaphores: Vec<vk::Semaphore>,
+    /// binary semaphores that can be exported as a sync_file
+    free_release_semaphores: Vec<vk::Semaphore>,
+    /// set once the kernel turned out to not support exporting sync_files from dmabufs
+    implicit_sync_unsupported: bool,
+    /// callbacks of dropped dmatexs and the semaphore value signaled once the gpu is done with
+    /// them
+    releasing: Vec<(u64, DropCallback)>,
+}
+
+struct BarrierSubmission {
+    buffer: vk::CommandBuffer,
+    /// the semaphore value signaled once the last submission of the command buffer is done
+    value: u64,
+    wait_semaphores: Vec<vk::Semaphore>,
+    release_semaphore: Option<vk::Semaphore>,
+    /// a release semaphore that wasn't exported is still signaled and can't be reused
+    release_exported: bool,
+    /// timeline semaphores of imported drm syncobjs, destroyed once the submission is done
+    syncobj_semaphores: Vec<vk::Semaphore>,
+}
+
+impl FromWorld for BarrierCommandBuffers {
+    fn from_world(world: &mut World) -> Self {
+        let device = world.resource::<RenderDevice>().clone();
+        let (
+            command_pool,
+            timeline_semaphore,
+            semaphore_fd,
+            queue_family_foreign,
+            syncobj_semaphores,
+        ) = unsafe {
+            device.wgpu_device().as_hal::<Vulkan, _, _>(|dev| {
+                let Some(dev) = dev else {
+                    return (vk::CommandPool::null(), vk::Semaphore::null(), None, false, false);
+                };
+                let vk_dev = dev.raw_device();
+                let command_pool = vk_dev
+                    .create_command_pool(
+                        &vk::CommandPoolCreateInfo {
+                            flags: vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
+                            queue_family_index: dev.queue_family_index(),
+                            ..Default::default()
+                        },
+                        None,
+                    )
+                    .inspect_err(|e| error!("Unable to create command pool: {e}"))
+                    .unwrap_or_default();
+                let mut timeline_info = vk::SemaphoreTypeCreateInfo::default()
+                    .semaphore_type(vk::SemaphoreType::TIMELINE)
+                    .initial_value(0);
+                let timeline_semaphore = vk_dev
+                    .create_semaphore(
+                        &vk::SemaphoreCreateInfo::default().push_next(&mut timeline_info),
+                        None,
+                    )
+                    .inspect_err(|err| error!("failed to create timeline semaphore: {err}"))
+                    .unwrap_or_default();
+                let instance = dev.shared_instance().raw_instance();
+                let semaphore_fd = instance
+                    .get_device_proc_addr(vk_dev.handle(), c"vkImportSemaphoreFdKHR".as_ptr())
+                    .is_some()
+                    .then(|| ash::khr::external_semaphore_fd::Device::new(instance, vk_dev));
+                if semaphore_fd.is_none() {
+                    debug!("VK_KHR_external_semaphore_fd is not enabled, implicit sync disabled");
+                }
+                // the extension has no functions, so it can't be detected by its proc addrs
+                let queue_family_foreign = dev
+                    .enabled_device_extensions()
+                    .contains(&ash::ext::queue_family_foreign::NAME);
+                let mut driver_properties = vk::PhysicalDeviceDriverProperties::default();
+                instance.get_physical_device_properties2(
+                    dev.raw_physical_device(),
+                    &mut vk::PhysicalDeviceProperties2::default().push_next(&mut driver_properties),
+                );
+                let syncobj_semaphores = driver_uses_drm_syncobjs(driver_properties.driver_id);
+                if !syncobj_semaphores {
+                    debug!(
+                        "driver {:?} doesn't implement OPAQUE_FD semaphores as drm syncobjs, explicit sync disabled",
+                        driver_properties.driver_id
+                    );
+                }
+                (
+                    command_pool,
+                    timeline_semaphore,
+                    semaphore_fd,
+                    queue_family_foreign,
+                    syncobj_semaphores,
+                )
+            })
+        };
+        BarrierCommandBuffers {
+            device,
+            command_pool,
+            timeline_semaphore,
+            next_value: 1,
+            submissions: Vec::new(),
+            semaphore_fd,
+            queue_family_foreign,
+            queue_family_foreign_warned: false,
+            syncobj_semaphores,
+            free_wait_semaphores: Vec::new(),
+            free_release_semaphores: Vec::new(),
+            implicit_sync_unsupported: false,
+            releasing: Vec::new(),
+        }
+    }
+}
+
+/// the mesa drivers implement OPAQUE_FD semaphores as drm syncobjs, other drivers like the
+/// proprietary nvidia one use a payload only they understand
+fn driver_uses_drm_syncobjs(driver_id: vk::DriverId) -> bool {
+    matches!(
+        driver_id,
+        vk::DriverId::MESA_RADV
+            | vk::DriverId::INTEL_OPEN_SOURCE_MESA
+            | vk::DriverId::MESA_TURNIP
+            | vk::DriverId::MESA_V3DV
+            | vk::DriverId::MESA_PANVK
+            | vk::DriverId::MESA_NVK
+            | vk::DriverId::IMAGINATION_OPEN_SOURCE_MESA
+            | vk::DriverId::MESA_AGXV
+    )
+}
+
+impl BarrierCommandBuffers {
+    /// a command buffer the gpu is done with, allocating a new one if all are still in use
+    unsafe fn get_command_buffer(
+        &mut self,
+        vk_dev: &ash::Device,
+    ) -> Result<(usize, vk::CommandBuffer), vk::Result> {
+        let completed = unsafe { vk_dev.get_semaphore_counter_value(self.timeline_semaphore)? };
+        if let Some(i) = self
+            .submissions
+            .iter()
+            .position(|submission| submission.value <= completed)
+        {
+            let submission = &mut self.submissions[i];
+            unsafe {
+                vk_dev.reset_command_buffer(
+                    submission.buffer,
+                    vk::CommandBufferResetFlags::empty(),
+                )?;
+            }
+            self.free_wait_semaphores
+                .append(&mut submission.wait_semaphores);
+            if let Some(semaphore) = submission.release_semaphore.take() {
+                match submission.release_exported {
+                    true => self.free_release_semaphores.push(semaphore),
+                    false => unsafe { vk_dev.destroy_semaphore(semaphore, None) },
+                }
+            }
+            for semaphore in submission.syncobj_semaphores.drain(..) {
+                unsafe { vk_dev.destroy_semaphore(semaphore, None) };
+            }
+            return Ok((i, submission.buffer));
+        }
+        let buffer = unsafe {
+            vk_dev.allocate_command_buffers(&vk::CommandBufferAllocateInfo {
+                command_pool: self.command_pool,
+                level: vk::CommandBufferLevel::PRIMARY,
+                command_buffer_count: 1,
+                ..Default::default()
+            })?
+        }
+        .into_iter()
+        .next()
+        .ok_or(vk::Result::ERROR_OUT_OF_HOST_MEMORY)?;
+        self.submissions.push(BarrierSubmission {
+            buffer,
+            value: 0,
+            wait_semaphores: Vec::new(),
+            release_semaphore: None,
+            release_exported: false,
+            syncobj_semaphores: Vec::new(),
+        });
+        Ok((self.submissions.len() - 1, buffer))
+    }
+
+    /// runs the callbacks of the dropped dmatexs the gpu is done with
+    unsafe fn run_release_callbacks(&mut self, vk_dev: &ash::Device) {
+        if self.releasing.is_empty() {
+            return;
+        }
+        let Ok(completed) =
+            (unsafe { vk_dev.get_semaphore_counter_value(self.timeline_semaphore) })
+                .inspect_err(|err| error!("failed to get semaphore value: {err}"))
+        else {
+            return;
+        };
+        // dropping the callbacks runs them
+        self.releasing.retain(|(value, _)| *value > completed);
+    }
+
+    /// the queue family index dmatexs are acquired from and released to, falls back to
+    /// VK_QUEUE_FAMILY_EXTERNAL if VK_EXT_queue_family_foreign isn't enabled
+    fn external_queue_family(&mut self, settings: &DmabufImportSettings) -> u32 {
+        match settings.external_queue_family {
+            ExternalQueueFamily::External => vk::QUEUE_FAMILY_EXTERNAL,
+            ExternalQueueFamily::Foreign if self.queue_family_foreign => {
+                vk::QUEUE_FAMILY_FOREIGN_EXT
+            }
+            ExternalQueueFamily::Foreign => {
+                if !self.queue_family_foreign_warned {
+                    warn!(
+                        "VK_EXT_queue_family_foreign is not enabled, using VK_QUEUE_FAMILY_EXTERNAL"
+                    );
+                    self.queue_family_foreign_warned = true;
+                }
+                vk::QUEUE_FAMILY_EXTERNAL
+            }
+        }
+    }
+
+    /// false if implicit sync is disabled or unsupported
+    fn implicit_sync(&self, settings: &DmabufImportSettings) -> bool {
+        settings.implicit_sync && self.semaphore_fd.is_some() && !self.implicit_sync_unsupported
+    }
+
+    /// a semaphore that the gpu waits on until the current implicit fences of the dmabuf are
+    /// signaled, see [`sync_file::export_sync_file`] for the `flags`
+    unsafe fn import_implicit_fence(
+        &mut self,
+        vk_dev: &ash::Device,
+        dmabuf: &OwnedFd,
+        flags: u32,
+    ) -> Option<vk::Semaphore> {
+        let semaphore_fd = self.semaphore_fd.as_ref()?;
+        let sync_file = match sync_file::export_sync_file(dmabuf.as_fd(), flags) {
+            Ok(sync_file) => sync_file,
+            Err(err) => {
+                if err.raw_os_error() == Some(libc::ENOTTY) {
+                    warn!("the kernel can't export dmabuf fences, implicit sync disabled");
+                    self.implicit_sync_unsupported = true;
+                } else {
+                    error!("failed to export dmabuf fence: {err}");
+                }
+                return None;
+            }
+        };
+        let semaphore = match self.free_wait_semaphores.pop() {
+            Some(semaphore) => semaphore,
+            None => unsafe { vk_dev.create_semaphore(&vk::SemaphoreCreateInfo::default(), None) }
+                .inspect_err(|err| error!("failed to create semaphore: {err}"))
+                .ok()?,
+        };
+        let result = unsafe {
+            semaphore_fd.import_semaphore_fd(
+                &vk::ImportSemaphoreFdInfoKHR::default()
+                    .semaphore(semaphore)
+                    .flags(vk::SemaphoreImportFlags::TEMPORARY)
+                    .handle_type(vk::ExternalSemaphoreHandleTypeFlags::SYNC_FD)
+                    .fd(sync_file.as_raw_fd()),
+            )
+        };
+        if let Err(err) = result {
+            error!("failed to import dmabuf fence: {err}");
+            self.free_wait_semaphores.push(semaphore);
+            return None;
+        }
+        // the semaphore owns the sync_file now
+        let _ = sync_file.into_raw_fd();
+        Some(semaphore)
+    }
+
+    /// imports the drm syncobj of an explicit sync timeline point as a timeline semaphore, mesa
+    /// drivers implement OPAQUE_FD semaphores as drm syncobjs
+    unsafe fn import_syncobj(
+        &self,
+        vk_dev: &ash::Device,
+        syncobj: OwnedFd,
+    ) -> Option<vk::Semaphore> {
+        let Some(semaphore_fd) = &self.semaphore_fd else {
+            error!("VK_KHR_external_semaphore_fd is not enabled, unable to use explicit sync");
+            return None;
+        };
+        // other drivers use their own OPAQUE_FD payloads and would reject or misinterpret the fd
+        if !self.syncobj_semaphores {
+            error!(
+                "the driver doesn't implement OPAQUE_FD semaphores as drm syncobjs, unable to use explicit sync"
+            );
+            return None;
+        }
+        let mut timeline_info =
+            vk::SemaphoreTypeCreateInfo::default().semaphore_type(vk::SemaphoreType::TIMELINE);
+        let semaphore = unsafe {
+            vk_dev.create_semaphore(
+                &vk::SemaphoreCreateInfo::default().push_next(&mut timeline_info),
+                None,
+            )
+        }
+        .inspect_err(|err| error!("failed to create timeline semaphore: {err}"))
+        .ok()?;
+        let result = unsafe {
+            semaphore_fd.import_semaphore_fd(
+                &vk::ImportSemaphoreFdInfoKHR::default()
+                    .semaphore(semaphore)
+                    .handle_type(vk::ExternalSemaphoreHandleTypeFlags::OPAQUE_FD)
+                    .fd(syncobj.as_raw_fd()),
+            )
+        };
+        if let Err(err) = result {
+            error!("failed to import syncobj: {err}");
+            unsafe { vk_dev.destroy_semaphore(semaphore, None) };
+            return None;
+        }
+        // the semaphore owns the syncobj now
+        let _ = syncobj.into_raw_fd();
+        Some(semaphore)
+    }
+
+    /// a semaphore that can be exported as a sync_file once a signal operation is submitted
+    unsafe fn get_release_semaphore(&mut self, vk_dev: &ash::Device) -> Option<vk::Semaphore> {
+        if let Some(semaphore) = self.free_release_semaphores.pop() {
+            return Some(semaphore);
+        }
+        let mut export_info = vk::ExportSemaphoreCreateInfo::default()
+            .handle_types(vk::ExternalSemaphoreHandleTypeFlags::SYNC_FD);
+        unsafe {
+            vk_dev.create_semaphore(
+                &vk::SemaphoreCreateInfo::default().push_next(&mut export_info),
+                None,
+            )
+        }
+        .inspect_err(|err| error!("failed to create semaphore: {err}"))
+        .ok()
+    }
+
+    /// exports the signaled release semaphore and attaches it to the dmabufs as a read or write
+    /// fence, so implicitly synced producers wait for us before accessing them again
+    unsafe fn attach_release_fence<'a>(
+        &mut self,
+        index: usize,
+        dmabufs: impl IntoIterator<Item = (&'a OwnedFd, u32)>,
+    ) {
+        let submission = &mut self.submissions[index];
+        let (Some(semaphore_fd), Some(semaphore)) =
+            (&self.semaphore_fd, submission.release_semaphore)
+        else {
+            return;
+        };
+        let fd = unsafe {
+            semaphore_fd.get_semaphore_fd(
+                &vk::SemaphoreGetFdInfoKHR::default()
+                    .semaphore(semaphore)
+                    .handle_type(vk::ExternalSemaphoreHandleTypeFlags::SYNC_FD),
+            )
+        };
+        let fd = match fd {
+            Ok(fd) => fd,
+            Err(err) => {
+                error!("failed to export release fence: {err}");
+                return;
+            }
+        };
+        submission.release_exported = true;
+        // -1 means the semaphore was already signaled, so there's nothing to wait for
+        if fd < 0 {
+            return;
+        }
+        let sync_file = unsafe { OwnedFd::from_raw_fd(fd) };
+        for (dmabuf, flags) in dmabufs {
+            if let Err(err) = sync_file::import_sync_file(dmabuf.as_fd(), sync_file.as_fd(), flags)
+            {
+                error!("failed to attach release fence to dmabuf: {err}");
+            }
+        }
+    }
+}
+
+impl Drop for BarrierCommandBuffers {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.wgpu_device().as_hal::<Vulkan, _, _>(|dev| {
+                let Some(dev) = dev else {
+                    return;
+                };
+                let vk_dev = dev.raw_device();
+                // the command buffers can only be freed once the gpu is done with them
+                let _ = vk_dev
+                    .wait_semaphores(
+                        &vk::SemaphoreWaitInfo::default()
+                            .values(&[self.next_value - 1])
+                            .semaphores(&[self.timeline_semaphore]),
+                        u64::MAX,
+                    )
+                    .inspect_err(|err| error!("failed to wait for semaphore: {err}"));
+                let semaphores = self.submissions.iter().flat_map(|submission| {
+                    submission
+                        .wait_semaphores
+                        .iter()
+                        .chain(&submission.release_semaphore)
+                        .chain(&submission.syncobj_semaphores)
+                });
+                for semaphore in semaphores
+                    .chain(&self.free_wait_semaphores)
+                    .chain(&self.free_release_semaphores)
+                {
+                    vk_dev.destroy_semaphore(*semaphore, None);
+                }
+                self.releasing.clear();
+                vk_dev.destroy_command_pool(self.command_pool, None);
+                vk_dev.destroy_semaphore(self.timeline_semaphore, None);
+            })
+        }
+    }
+}
+
 fn memory_barrier(
     device: &RenderDevice,
     dmatexs: &ImportedDmatexs,
+    settings: &DmabufImportSettings,
+    command_buffers: &mut BarrierCommandBuffers,
     queue_transfer_direction: ImageQueueTransfer,
 ) {
     unsafe {
@@ -187,40 +1277,97 @@ fn memory_barrier(
                 return;
             };
             let vk_dev = dev.raw_device();
-            let Ok(command_pool) = vk_dev
-                .create_command_pool(
-                    &vk::CommandPoolCreateInfo {
-                        flags: vk::CommandPoolCreateFlags::TRANSIENT,
-                        queue_family_index: dev.queue_family_index(),
-                        ..Default::default()
-                    },
-                    None,
-                )
-                .inspect_err(|e| error!("Unable to create command pool: {e}"))
+            command_buffers.run_release_callbacks(vk_dev);
+            let Ok(texes) = dmatexs
+                .dmatexs
+                .lock()
+                .inspect_err(|e| error!("Unable to lock dmatexs: {e}"))
             else {
                 return;
             };
-
-            let Ok(Some(buffer)) = dev
-                .raw_device()
-                .allocate_command_buffers(&vk::CommandBufferAllocateInfo {
-                    command_pool,
-                    level: vk::CommandBufferLevel::PRIMARY,
-                    command_buffer_count: 1,
-                    ..Default::default()
+            // removed dmatexs might still be owned by our queue, they're kept alive until the
+            // release barrier is done
+            let removed = match queue_transfer_direction {
+                ImageQueueTransfer::Acquire => Vec::new(),
+                ImageQueueTransfer::Release => dmatexs
+                    .removed
+                    .lock()
+                    .map(|mut removed| std::mem::take(&mut *removed))
+                    .inspect_err(|e| error!("Unable to lock removed dmatexs: {e}"))
+                    .unwrap_or_default(),
+            };
+            // cached imports can be shared by multiple dmatexs, the shared ownership state makes
+            // sure every image only gets one barrier
+            let mut textures = Vec::<&ImportedTexture>::new();
+            for tex in texes.values().chain(&removed).filter_map(|v| match v {
+                DmaImage::UnImported(_, _, _) | DmaImage::CpuUploaded(_) | DmaImage::Failed(_) => {
+                    None
+                }
+                DmaImage::Imported(imported_texture) => Some(imported_texture),
+            }) {
+                let acquired = tex.acquired.load(Ordering::Relaxed);
+                let needs_barrier = match queue_transfer_direction {
+                    ImageQueueTransfer::Acquire => !acquired,
+                    ImageQueueTransfer::Release => acquired,
+                };
+                if needs_barrier
+                    && !textures
+                        .iter()
+                        .any(|v| Arc::ptr_eq(&v.acquired, &tex.acquired))
+                {
+                    textures.push(tex);
+                }
+            }
+            let images = textures
+                .iter()
+                .flat_map(|i| {
+                    iter::once(&i.texture)
+                        .chain(&i.plane_textures)
+                        .map(|t| (t, i.usage))
                 })
-                .inspect_err(|e| error!("Unable to allocate command buffer: {e}"))
-                .map(|v| v.into_iter().next())
-            else {
-                vk_dev.destroy_command_pool(command_pool, None);
-                return;
+                .filter_map(|(t, usage)| {
+                    t.as_hal::<Vulkan, _, _>(|i| i.map(|i| (i.raw_handle(), usage)))
+                })
+                .collect::<Vec<_>>();
+            // explicitly synced dmatexs wait for their acquire point before the first use and
+            // signal their release point once they're dropped
+            let acquire_points = match queue_transfer_direction {
+                ImageQueueTransfer::Acquire => texes
+                    .values()
+                    .filter_map(|v| match v {
+                        DmaImage::Imported(tex) => tex.acquire_point.as_ref(),
+                        DmaImage::UnImported(_, _, _)
+                        | DmaImage::CpuUploaded(_)
+                        | DmaImage::Failed(_) => None,
+                    })
+                    .filter(|point| !point.waited.load(Ordering::Relaxed))
+                    .collect::<Vec<_>>(),
+                ImageQueueTransfer::Release => Vec::new(),
             };
-            let Ok(texes) = dmatexs
-                .0
-                .lock()
-                .inspect_err(|e| error!("Unable to lock dmatexs: {e}"))
+            // submitted after the frame, so the gpu is done with dropped dmatexs once it's done
+            let releases = match queue_transfer_direction {
+                ImageQueueTransfer::Acquire => Vec::new(),
+                ImageQueueTransfer::Release => dmatexs
+                    .releases
+                    .lock()
+                    .map(|mut releases| std::mem::take(&mut *releases))
+                    .inspect_err(|e| error!("Unable to lock pending dmatex releases: {e}"))
+                    .unwrap_or_default(),
+            };
+            if images.is_empty()
+                && acquire_points.is_empty()
+                && releases.is_empty()
+                && removed.is_empty()
+            {
+                return;
+            }
+            let external_queue_family = command_buffers.external_queue_family(settings);
+
+            let Ok((index, buffer)) = command_buffers
+                .get_command_buffer(vk_dev)
+                .inspect_err(|e| error!("Unable to get command buffer: {e}"))
             else {
-                vk_dev.destroy_command_pool(command_pool, None);
+                restore_releases(dmatexs, releases, removed);
                 return;
             };
 
@@ -235,53 +1382,54 @@ fn memory_barrier(
                 .inspect_err(|err| error!("failed to begin command buffer: {err}"))
                 .is_err()
             {
-                vk_dev.destroy_command_pool(command_pool, None);
+                restore_releases(dmatexs, releases, removed);
                 return;
             }
 
             let vk_submit_span = debug_span!("VK dmatex image acquire").entered();
-            for image in texes
-                .iter()
-                .filter_map(|v| match v.1 {
-                    DmaImage::UnImported(_, _, _) => None,
-                    DmaImage::Imported(imported_texture) => Some(imported_texture),
-                })
-                .filter_map(|i| {
-                    i.texture
-                        .as_hal::<Vulkan, _, _>(|i| i.map(|i| i.raw_handle()))
-                })
-            {
+            for (image, usage) in images {
+                let (layout, stage, access) = usage.barrier_info();
+                let subresource_range = vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                };
+                // the producer owns the image in the GENERAL layout, while we own it, it stays in
+                // the layout wgpu uses for the usage
+                let (src_stage, dst_stage, barrier) = match queue_transfer_direction {
+                    ImageQueueTransfer::Acquire => (
+                        vk::PipelineStageFlags::TOP_OF_PIPE,
+                        stage,
+                        vk::ImageMemoryBarrier::default()
+                            .src_access_mask(vk::AccessFlags::NONE)
+                            .dst_access_mask(access)
+                            .old_layout(vk::ImageLayout::GENERAL)
+                            .new_layout(layout)
+                            .src_queue_family_index(external_queue_family)
+                            .dst_queue_family_index(dev.queue_family_index()),
+                    ),
+                    ImageQueueTransfer::Release => (
+                        stage,
+                        vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                        vk::ImageMemoryBarrier::default()
+                            .src_access_mask(access)
+                            .dst_access_mask(vk::AccessFlags::NONE)
+                            .old_layout(layout)
+                            .new_layout(vk::ImageLayout::GENERAL)
+                            .src_queue_family_index(dev.queue_family_index())
+                            .dst_queue_family_index(external_queue_family),
+                    ),
+                };
                 vk_dev.cmd_pipeline_barrier(
                     buffer,
-                    vk::PipelineStageFlags::TOP_OF_PIPE,
-                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    src_stage,
+                    dst_stage,
                     vk::DependencyFlags::empty(),
                     &[],
                     &[],
-                    &[vk::ImageMemoryBarrier {
-                        src_access_mask: vk::AccessFlags::NONE,
-                        dst_access_mask: vk::AccessFlags::NONE,
-                        old_layout: vk::ImageLayout::GENERAL,
-                        new_layout: vk::ImageLayout::GENERAL,
-                        // TODO: might want to use vk::QUEUE_FAMILY_FOREIGN_EXT instead
-                        src_queue_family_index: match queue_transfer_direction {
-                            ImageQueueTransfer::Acquire => vk::QUEUE_FAMILY_EXTERNAL,
-                            ImageQueueTransfer::Release => dev.queue_family_index(),
-                        },
-                        dst_queue_family_index: match queue_transfer_direction {
-                            ImageQueueTransfer::Acquire => dev.queue_family_index(),
-                            ImageQueueTransfer::Release => vk::QUEUE_FAMILY_EXTERNAL,
-                        },
-                        image,
-                        subresource_range: vk::ImageSubresourceRange {
-                            aspect_mask: vk::ImageAspectFlags::COLOR,
-                            base_mip_level: 0,
-                            level_count: 1,
-                            base_array_layer: 0,
-                            layer_count: 1,
-                        },
-                        ..Default::default()
-                    }],
+                    &[barrier.image(image).subresource_range(subresource_range)],
                 );
             }
             drop(vk_submit_span);
@@ -290,76 +1438,160 @@ fn memory_barrier(
                 .inspect_err(|err| error!("failed to end command buffer: {err}"))
                 .is_err()
             {
-                vk_dev.destroy_command_pool(command_pool, None);
+                restore_releases(dmatexs, releases, removed);
                 return;
             }
 
-            // let fence = vk_dev
-            //     .create_fence(
-            //         &vk::FenceCreateInfo {
-            //             flags: vk::FenceCreateFlags::empty(),
-            //             ..Default::default()
-            //         },
-            //         None,
-            //     )
-            //     .unwrap();
-            let mut timeline_info =
-                vk::SemaphoreTypeCreateInfo::default().semaphore_type(vk::SemaphoreType::TIMELINE);
-            let Ok(timeline_semaphore) = vk_dev
-                .create_semaphore(
-                    &vk::SemaphoreCreateInfo::default().push_next(&mut timeline_info),
-                    None,
-                )
-                .inspect_err(|err| error!("failed to create timeline semaphore: {err}"))
-            else {
-                vk_dev.destroy_command_pool(command_pool, None);
-                return;
-            };
-            let mut timeline_info =
-                vk::TimelineSemaphoreSubmitInfo::default().signal_semaphore_values(&[2]);
-            if vk_dev
+            // implicitly synced producers only fence their accesses on the dmabufs, so the acquire
+            // waits on those fences and the release signals a fence we attach to them
+            let implicit_sync = command_buffers.implicit_sync(settings);
+            let dmabufs = textures
+                .iter()
+                .filter(|tex| tex.acquire_point.is_none())
+                .flat_map(|tex| {
+                    let flags = tex.usage.sync_file_flags();
+                    tex.dmabufs.iter().map(move |dmabuf| (dmabuf, flags))
+                });
+            let mut wait_semaphores = Vec::new();
+            let mut release_semaphore = None;
+            if implicit_sync {
+                match queue_transfer_direction {
+                    ImageQueueTransfer::Acquire => {
+                        for (dmabuf, flags) in dmabufs.clone() {
+                            if let Some(semaphore) =
+                                command_buffers.import_implicit_fence(vk_dev, dmabuf, flags)
+                            {
+                                wait_semaphores.push(semaphore);
+                            }
+                        }
+                    }
+                    ImageQueueTransfer::Release => {
+                        release_semaphore = command_buffers.get_release_semaphore(vk_dev);
+                    }
+                }
+            }
+            let mut syncobj_semaphores = Vec::new();
+            // binary semaphores ignore their value
+            let mut wait_values = vec![0; wait_semaphores.len()];
+            let mut all_wait_semaphores = wait_semaphores.clone();
+            for point in &acquire_points {
+                let Ok(syncobj) = point.point.syncobj_fd.as_fd().try_clone_to_owned() else {
+                    error!("unable to dup acquire syncobj");
+                    continue;
+                };
+                if let Some(semaphore) = command_buffers.import_syncobj(vk_dev, syncobj) {
+                    syncobj_semaphores.push(semaphore);
+                    all_wait_semaphores.push(semaphore);
+                    wait_values.push(point.point.point);
+                }
+            }
+            let wait_stages = vec![vk::PipelineStageFlags::ALL_COMMANDS; all_wait_semaphores.len()];
+            let value = command_buffers.next_value;
+            let mut signal_semaphores = vec![command_buffers.timeline_semaphore];
+            let mut signal_values = vec![value];
+            if let Some(semaphore) = release_semaphore {
+                signal_semaphores.push(semaphore);
+                signal_values.push(0);
+            }
+            for point in releases.iter().filter_map(|release| release.point.as_ref()) {
+                let Ok(syncobj) = point.syncobj_fd.as_fd().try_clone_to_owned() else {
+                    error!("unable to dup release syncobj");
+                    continue;
+                };
+                if let Some(semaphore) = command_buffers.import_syncobj(vk_dev, syncobj) {
+                    syncobj_semaphores.push(semaphore);
+                    signal_semaphores.push(semaphore);
+                    signal_values.push(point.point);
+                }
+            }
+
+            // submitted to the same queue as the frame, so submission order makes the barriers
+            // apply to the frame without waiting on the cpu
+            let mut timeline_info = vk::TimelineSemaphoreSubmitInfo::default()
+                .wait_semaphore_values(&wait_values)
+                .signal_semaphore_values(&signal_values);
+            let submitted = vk_dev
                 .queue_submit(
                     dev.raw_queue(),
                     &[vk::SubmitInfo::default()
+                        .wait_semaphores(&all_wait_semaphores)
+                        .wait_dst_stage_mask(&wait_stages)
                         .command_buffers(&[buffer])
-                        .signal_semaphores(&[timeline_semaphore])
+                        .signal_semaphores(&signal_semaphores)
                         .push_next(&mut timeline_info)],
                     vk::Fence::null(),
                 )
                 .inspect_err(|err| error!("failed to submit queue: {err}"))
-                .is_err()
-            {
-                vk_dev.destroy_command_pool(command_pool, None);
-                vk_dev.destroy_semaphore(timeline_semaphore, None);
+                .is_ok();
+            let submission = &mut command_buffers.submissions[index];
+            submission.wait_semaphores = wait_semaphores;
+            submission.release_semaphore = release_semaphore;
+            submission.syncobj_semaphores = syncobj_semaphores;
+            // an unsubmitted release semaphore is still unsignaled
+            submission.release_exported = !submitted;
+            if !submitted {
+                restore_releases(dmatexs, releases, removed);
                 return;
-            };
-            if vk_dev
-                .wait_semaphores(
-                    &vk::SemaphoreWaitInfo::default()
-                        .values(&[2])
-                        .semaphores(&[timeline_semaphore]),
-                    u64::MAX,
-                )
-                .inspect_err(|err| error!("failed to wait for semaphore: {err}"))
-                .is_err()
-            {
-                vk_dev.destroy_command_pool(command_pool, None);
-                vk_dev.destroy_semaphore(timeline_semaphore, None);
-                return;
-            };
-            vk_dev.destroy_semaphore(timeline_semaphore, None);
-            vk_dev.destroy_command_pool(command_pool, None);
+            }
+            submission.value = value;
+            command_buffers.next_value += 1;
+            for release in releases {
+                command_buffers.releasing.push((value, release.on_release));
+            }
+            for point in acquire_points {
+                point.waited.store(true, Ordering::Relaxed);
+            }
+            command_buffers.attach_release_fence(index, dmabufs);
+            for tex in textures {
+                tex.acquired.store(
+                    matches!(queue_transfer_direction, ImageQueueTransfer::Acquire),
+                    Ordering::Relaxed,
+                );
+            }
+            if !removed.is_empty() {
+                command_buffers
+                    .releasing
+                    .push((value, DropCallback(Some(Box::new(move || drop(removed))))));
+            }
         })
     };
 }
 
+/// puts the releases and removed dmatexs taken for a release submission back if it couldn't be
+/// submitted, so the next one releases them instead of them being dropped while the gpu might
+/// still use them
+fn restore_releases(
+    dmatexs: &ImportedDmatexs,
+    releases: Vec<PendingRelease>,
+    removed: Vec<DmaImage>,
+) {
+    if !releases.is_empty() {
+        match dmatexs.releases.lock() {
+            Ok(mut pending) => drop(pending.splice(0..0, releases)),
+            Err(e) => error!("Unable to lock pending dmatex releases: {e}"),
+        }
+    }
+    if !removed.is_empty() {
+        match dmatexs.removed.lock() {
+            Ok(mut pending) => drop(pending.splice(0..0, removed)),
+            Err(e) => error!("Unable to lock removed dmatexs: {e}"),
+        }
+    }
+}
+
 fn insert_dmatex_into_gpu_images(
     mut gpu_images: ResMut<RenderAssets<GpuImage>>,
     imported: Res<ImportedDmatexs>,
     device: Res<RenderDevice>,
+    mut converter: ResMut<DmatexConverter>,
+    settings: Res<DmabufImportSettings>,
 ) {
     #[expect(clippy::unwrap_used)]
-    let mut imported = imported.0.lock().unwrap();
+    let mut cache = imported.cache.lock().unwrap();
+    #[expect(clippy::unwrap_used)]
+    let mut import_events = imported.import_events.lock().unwrap();
+    #[expect(clippy::unwrap_used)]
+    let mut imported = imported.dmatexs.lock().unwrap();
     let handles = imported.keys().cloned().collect::<Vec<_>>();
     for handle in handles {
         // filter out outdated dmatexs
@@ -370,13 +1602,22 @@ fn insert_dmatex_into_gpu_images(
         if matches!(imported.get(&handle), Some(DmaImage::UnImported(_, _, _)))
             && let Some(DmaImage::UnImported(dmabuf, on_drop, usage)) = imported.remove(&handle)
         {
-            match import_texture(&device, dmabuf, on_drop, usage) {
+            match import_dmatex(
+                &device,
+                &mut converter,
+                &settings,
+                &mut cache,
+                dmabuf,
+                on_drop,
+                usage,
+            ) {
                 Ok(tex) => {
-                    debug!("imported dmatex");
-                    imported.insert(handle.clone(), DmaImage::Imported(tex));
+                    imported.insert(handle.clone(), tex);
+                    import_events.push(ImportEvent::Imported(handle.clone()));
                 }
                 Err(err) => {
-                    error!("failed to import dmatex: {err}");
+                    import_events.push(ImportEvent::Failed(handle.clone(), err.clone()));
+                    imported.insert(handle.clone(), DmaImage::Failed(err));
                     continue;
                 }
             }
@@ -386,29 +1627,190 @@ fn insert_dmatex_into_gpu_images(
             continue;
         };
 
-        if let Some(DmaImage::Imported(tex)) = imported.get(&handle) {
-            debug!("setting texture view!");
-            render_tex.texture_view = tex.texture_view.clone();
-            render_tex.size = tex.texture.size();
-            render_tex.mip_level_count = tex.texture.mip_level_count();
-            render_tex.texture = tex.texture.clone();
-        } else {
-            error!("unreachable");
+        let (texture, texture_view) = match imported.get(&handle) {
+            Some(DmaImage::Imported(tex)) => (tex.output_texture(), tex.output_view()),
+            Some(DmaImage::CpuUploaded(tex)) => (tex.texture(), tex.view()),
+            Some(DmaImage::Failed(_)) => continue,
+            _ => {
+                error!("unreachable");
+                continue;
+            }
+        };
+        debug!("setting texture view!");
+        render_tex.texture_view = texture_view;
+        render_tex.texture_format = texture.format();
+        render_tex.size = texture.size();
+        render_tex.mip_level_count = texture.mip_level_count();
+        render_tex.texture = texture;
+    }
+}
+
+/// imports the dmatex, reusing a cached import of the same dmabuf and falling back to a cpu upload
+/// if enabled
+fn import_dmatex(
+    device: &RenderDevice,
+    converter: &mut DmatexConverter,
+    settings: &DmabufImportSettings,
+    cache: &mut ImportCache,
+    mut dmabuf: Dmatex,
+    on_drop: DropCallback,
+    usage: DmatexUsage,
+) -> Result<DmaImage, DmatexImportError> {
+    // the release point is already part of the drop callback
+    let acquire_point = dmabuf.sync.0.take().map(|sync| {
+        Arc::new(AcquirePoint {
+            point: sync.acquire,
+            waited: default(),
+        })
+    });
+    let info = DmatexInfo::new(&dmabuf);
+    let cache_key = (settings.import_cache_frames != 0)
+        .then(|| ImportCacheKey::new(&dmabuf, usage))
+        .transpose()
+        .inspect_err(|err| warn!("unable to identify dmabuf for the import cache: {err}"))
+        .ok()
+        .flatten();
+    if let Some(key) = &cache_key
+        && let Some(mut tex) = cache.get(key)
+    {
+        debug!("reusing cached dmatex import");
+        tex._on_release = Some(Arc::new(on_drop));
+        tex.acquire_point = acquire_point;
+        tex.info = Some(info);
+        return Ok(DmaImage::Imported(Box::new(tex)));
+    }
+    // keep the dmabuf around in case the import fails
+    let fallback_buf = (settings.cpu_upload_fallback && !usage.writes())
+        .then(|| dmabuf.try_clone())
+        .transpose()
+        .inspect_err(|err| warn!("unable to clone dmatex for the cpu fallback: {err}"))
+        .ok()
+        .flatten();
+    // the callback is tied to the dmatex instead of the texture, so it runs once the gpu is done
+    // with the dmatex instead of once wgpu destroys the texture
+    match import_texture(
+        device,
+        dmabuf,
+        DropCallback(None),
+        usage,
+        settings.implicit_modifier,
+    ) {
+        Ok(mut tex) => {
+            debug!("imported dmatex");
+            tex.prepare_conversion(device, converter);
+            if let Some(key) = cache_key {
+                cache.insert(key, tex.clone());
+            }
+            tex._on_release = Some(Arc::new(on_drop));
+            tex.acquire_point = acquire_point;
+            tex.info = Some(info);
+            Ok(DmaImage::Imported(Box::new(tex)))
+        }
+        Err(err) => {
+            error!("failed to import dmatex: {err}");
+            if matches!(
+                err.error,
+                ImportError::ModifierInvalid
+                    | ImportError::StorageUnsupported
+                    | ImportError::ResolutionTooLarge
+                    | ImportError::ModifiersUnsupported
+                    | ImportError::ImplicitModifierUnsupported
+                    | ImportError::MissingFeatures(_)
+                    | ImportError::ImageUnsupported { .. }
+            ) && let Some(plane) = err.dmatex.planes.get(err.plane.unwrap_or(0))
+            {
+                let report = DmatexSupportReport::new(
+                    device,
+                    err.dmatex.format,
+                    plane.modifier,
+                    usage,
+                    Some(err.dmatex.res),
+                    settings.implicit_modifier,
+                );
+                debug!("{report}");
+            }
+            let Some(buf) = fallback_buf else {
+                return Err(err);
+            };
+            match CpuUploadedTexture::new(device, buf, on_drop) {
+                Ok(tex) => {
+                    debug!("uploading dmatex from the cpu");
+                    Ok(DmaImage::CpuUploaded(Box::new(tex)))
+                }
+                Err(fallback_err) => {
+                    error!("failed to upload dmatex from the cpu: {fallback_err}");
+                    Err(err)
+                }
+            }
         }
     }
 }
 
-fn get_handle(images: &mut Assets<Image>, buf: &Dmatex) -> Result<Handle<Image>, ImportError> {
-    let desc = get_imported_descriptor(buf)?;
-    Ok(images.add(Image::new_uninit(
-        desc.size,
-        desc.dimension,
-        desc.format,
+fn evict_unused_dmatex_imports(
+    imported: Res<ImportedDmatexs>,
+    settings: Res<DmabufImportSettings>,
+) {
+    let Ok(mut cache) = imported
+        .cache
+        .lock()
+        .inspect_err(|e| error!("Unable to lock dmatex import cache: {e}"))
+    else {
+        return;
+    };
+    cache.evict_unused(settings.import_cache_frames);
+}
+
+/// the placeholder image the dmatex gets inserted into, it has the size and format of the texture
+/// that ends up in the [`GpuImage`]
+fn get_image(
+    buf: &Dmatex,
+    usage: DmatexUsage,
+    cpu_upload_fallback: bool,
+) -> Result<Image, ImportError> {
+    let convert_params = get_convert_params(buf, get_vulkan_format(buf)?);
+    if usage.writes() && !convert_params.is_identity() {
+        return Err(ImportError::ConversionNotWritable);
+    }
+    let size = wgpu::Extent3d {
+        width: buf.res.x,
+        height: buf.res.y,
+        depth_or_array_layers: 1,
+    };
+    // Y′CbCr dmatexs might not have a wgpu format, but the converted texture always does
+    let format = match convert_params.fixed_output_format() {
+        Some(format) => format,
+        None => match get_imported_descriptor(buf, usage) {
+            Ok(desc) => desc.format,
+            // might still be importable through the cpu fallback
+            Err(err) if cpu_upload_fallback && !usage.writes() => {
+                cpu_upload_format(buf).ok_or(err)?
+            }
+            Err(err) => return Err(err),
+        },
+    };
+    let mut image = Image::new_uninit(
+        convert_params.output_size(size),
+        wgpu::TextureDimension::D2,
+        format,
         RenderAssetUsages::RENDER_WORLD,
-    )))
+    );
+    // the placeholder is rendered into until the dmatex is imported
+    if usage == DmatexUsage::RenderTarget {
+        image.texture_descriptor.usage |= TextureUsages::RENDER_ATTACHMENT;
+    }
+    // formats that need a device feature for storage only get bound as storage once imported
+    if usage == DmatexUsage::Storage
+        && format
+            .guaranteed_format_features(wgpu::Features::empty())
+            .allowed_usages
+            .contains(TextureUsages::STORAGE_BINDING)
+    {
+        image.texture_descriptor.usage |= TextureUsages::STORAGE_BINDING;
+    }
+    Ok(image)
 }
 
-#[derive(Error, Debug, Clone, Copy)]
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ImportError {
     #[error("Format is not compatible with Vulkan")]
     VulkanIncompatibleFormat,
@@ -434,18 +1836,141 @@ pub enum ImportError {
     IncorrectNumberOfPlanes,
     #[error("No Planes to Import")]
     NoPlanes,
+    #[error("The offset or stride of the DmaTex plane does not match the layout the driver uses")]
+    PlaneLayoutMismatch,
+    #[error(
+        "The DmaTex planes are in separate dmabufs, but the modifier doesn't support disjoint planes"
+    )]
+    NonDisjointPlanesInSeparateBuffers,
+    #[error("Only LINEAR dmabufs can be uploaded from the cpu")]
+    NotLinear,
+    #[error("Format can't be converted on the cpu")]
+    CpuIncompatibleFormat,
+    #[error("The dmabuf is smaller than the DmaTex plane")]
+    DmabufTooSmall,
+    #[error("Unable to map dmabuf: {}", std::io::Error::from_raw_os_error(*.0))]
+    MmapFailed(i32),
+    #[error("Unable to sync dmabuf for cpu access: {}", std::io::Error::from_raw_os_error(*.0))]
+    DmabufSyncFailed(i32),
+    #[error("Unable to dup fd: {}", std::io::Error::from_raw_os_error(*.0))]
+    FdDupFailed(i32),
+    #[error("Dmatexs that are written to can't be transformed or color converted")]
+    ConversionNotWritable,
+    #[error("Wgpu doesn't support storage textures of the Format")]
+    StorageUnsupported,
+    #[error("The device doesn't support drm modifiers, only LINEAR can be imported")]
+    ModifiersUnsupported,
+    #[error("The driver can't import the implicit modifier")]
+    ImplicitModifierUnsupported,
+    #[error("The modifier is missing the format features {0:?}")]
+    MissingFeatures(FormatFeatureFlags2),
+    #[error("The driver can't create images with usage {usage:?} and flags {flags:?}")]
+    ImageUnsupported {
+        usage: vk::ImageUsageFlags,
+        flags: vk::ImageCreateFlags,
+    },
+    #[error("The resolution is larger than the Format and Modifier support")]
+    ResolutionTooLarge,
+    #[error("The resolution is zero or absurdly large")]
+    InvalidResolution,
+    #[error("The DmaTex plane fd is not a dmabuf")]
+    NotADmabuf,
+    #[error("The offset or stride of the DmaTex plane is out of bounds")]
+    InvalidPlaneLayout,
+    #[error("Unable to query dmabuf fd: {}", std::io::Error::from_raw_os_error(*.0))]
+    FdQueryFailed(i32),
+}
+
+impl ImportError {
+    pub(crate) fn in_plane(self, plane: usize) -> PlaneError {
+        PlaneError {
+            error: self,
+            plane: Some(plane),
+        }
+    }
+}
+
+/// an [`ImportError`] and the plane that caused it, before the metadata of the dmatex is attached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PlaneError {
+    pub(crate) error: ImportError,
+    plane: Option<usize>,
+}
+
+impl From<ImportError> for PlaneError {
+    fn from(error: ImportError) -> Self {
+        PlaneError { error, plane: None }
+    }
+}
+
+/// an [`ImportError`] with the metadata of the dmatex that caused it
+#[derive(Debug, Clone)]
+pub struct DmatexImportError {
+    pub error: ImportError,
+    /// the plane that caused the error, None if the error isn't specific to a plane
+    pub plane: Option<usize>,
+    pub dmatex: DmatexInfo,
 }
 
-fn get_imported_descriptor(buf: &Dmatex) -> Result<wgpu::TextureDescriptor<'static>, ImportError> {
+impl DmatexImportError {
+    pub(crate) fn new(error: impl Into<PlaneError>, dmatex: DmatexInfo) -> Self {
+        let PlaneError { error, plane } = error.into();
+        DmatexImportError {
+            error,
+            plane,
+            dmatex,
+        }
+    }
+}
+
+impl Display for DmatexImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.plane {
+            Some(plane) => write!(f, "{} (plane {plane} of {})", self.error, self.dmatex),
+            None => write!(f, "{} ({})", self.error, self.dmatex),
+        }
+    }
+}
+
+impl std::error::Error for DmatexImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+pub(crate) fn get_vulkan_format(buf: &Dmatex) -> Result<vk::Format, ImportError> {
     let vulkan_format = drm_fourcc_to_vk_format(
         DrmFourcc::try_from(buf.format).map_err(ImportError::UnrecognizedFourcc)?,
     )
     .ok_or(ImportError::VulkanIncompatibleFormat)?;
-    let vulkan_format = buf
+    Ok(buf
         .srgb
         .then(|| vk_format_to_srgb(vulkan_format))
         .flatten()
-        .unwrap_or(vulkan_format);
+        .unwrap_or(vulkan_format))
+}
+
+fn get_convert_params(buf: &Dmatex, vulkan_format: vk::Format) -> ConvertParams {
+    ConvertParams {
+        transform: buf.transform,
+        ycbcr: vk_format_ycbcr_info(vulkan_format).map(|info| YcbcrParams {
+            plane_count: info.planes.len() as u32,
+            bit_depth: info.bit_depth,
+            chroma_subsampling: info.chroma_subsampling,
+            chroma_siting: buf.chroma_siting,
+            encoding: buf.ycbcr_encoding,
+            range: buf.ycbcr_range,
+        }),
+        primaries: buf.color_primaries,
+        transfer: buf.transfer_function,
+    }
+}
+
+fn get_imported_descriptor(
+    buf: &Dmatex,
+    usage: DmatexUsage,
+) -> Result<wgpu::TextureDescriptor<'static>, ImportError> {
+    let vulkan_format = get_vulkan_format(buf)?;
     Ok(wgpu::TextureDescriptor {
         label: None,
         size: wgpu::Extent3d {
@@ -457,19 +1982,39 @@ fn get_imported_descriptor(buf: &Dmatex) -> Result<wgpu::TextureDescriptor<'stat
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
         format: vulkan_to_wgpu(vulkan_format).ok_or(ImportError::WgpuIncompatibleFormat)?,
-        usage: TextureUsages::RENDER_ATTACHMENT
-            | TextureUsages::TEXTURE_BINDING
-            | TextureUsages::COPY_SRC
-            | TextureUsages::COPY_DST,
+        usage: usage.texture_usage(),
         view_formats: &[],
     })
 }
 
+/// only waited on before the first use of the dmatex, afterwards the point stays signaled
+#[derive(Debug)]
+struct AcquirePoint {
+    point: DmatexTimelinePoint,
+    waited: AtomicBool,
+}
+
 #[derive(Clone, Debug)]
 pub struct ImportedTexture {
     texture: Texture,
     texture_view: TextureView,
-    _usage: DmatexUsage,
+    /// views of the individual planes of multi-planar textures
+    plane_views: Vec<TextureView>,
+    /// planes that were imported as separate textures, `texture` holds the first plane
+    plane_textures: Vec<Texture>,
+    convert_params: ConvertParams,
+    converted: Option<ConvertedTexture>,
+    usage: DmatexUsage,
+    /// if the texture is currently owned by our queue, shared with the clones in the import cache
+    acquired: Arc<AtomicBool>,
+    /// the distinct dmabufs of the planes, for bridging implicit sync
+    dmabufs: Arc<[OwnedFd]>,
+    /// explicit sync acquire point of the dmatex, the implicit fences are ignored if set
+    acquire_point: Option<Arc<AcquirePoint>>,
+    /// None for textures that weren't imported from a dmatex set through [`ImportedDmatexs`]
+    info: Option<DmatexInfo>,
+    /// callback of the dmatex, cached textures outlive the dmatex they were imported for
+    _on_release: Option<Arc<DropCallback>>,
 }
 
 impl ImportedTexture {
@@ -477,15 +2022,51 @@ impl ImportedTexture {
         ImportedTexture {
             texture,
             texture_view,
-            _usage: DmatexUsage::Sampling,
+            plane_views: Vec::new(),
+            plane_textures: Vec::new(),
+            convert_params: ConvertParams::default(),
+            converted: None,
+            usage: DmatexUsage::Sampling,
+            acquired: default(),
+            dmabufs: default(),
+            acquire_point: None,
+            info: None,
+            _on_release: None,
         }
     }
+    /// the texture backed by the dmabuf
     pub fn texture(&self) -> Texture {
         self.texture.clone()
     }
     pub fn view(&self) -> TextureView {
         self.texture_view.clone()
     }
+    /// the texture that ends up in the [`GpuImage`], this is the converted texture if the dmatex
+    /// needed any conversion (e.g. rotating or Y′CbCr to RGB)
+    pub fn output_texture(&self) -> Texture {
+        match &self.converted {
+            Some(converted) => converted.texture(),
+            None => self.texture(),
+        }
+    }
+    pub fn output_view(&self) -> TextureView {
+        match &self.converted {
+            Some(converted) => converted.view(),
+            None => self.view(),
+        }
+    }
+    fn prepare_conversion(&mut self, device: &RenderDevice, converter: &mut DmatexConverter) {
+        if self.convert_params.is_identity() {
+            return;
+        }
+        let plane_view = |i: usize| self.plane_views.get(i).unwrap_or(&self.texture_view);
+        self.converted = Some(converter.create_converted(
+            device,
+            &self.texture,
+            [plane_view(0), plane_view(1), plane_view(2)],
+            self.convert_params,
+        ));
+    }
 }
 
 #[tracing::instrument(level = "debug", skip(device, on_drop))]
@@ -494,60 +2075,605 @@ pub fn import_texture(
     buf: Dmatex,
     on_drop: DropCallback,
     usage: DmatexUsage,
-) -> Result<ImportedTexture, ImportError> {
-    let vulkan_format = drm_fourcc_to_vk_format(
-        DrmFourcc::try_from(buf.format).map_err(ImportError::UnrecognizedFourcc)?,
+    implicit_modifier: ImplicitModifierPolicy,
+) -> Result<ImportedTexture, DmatexImportError> {
+    let info = DmatexInfo::new(&buf);
+    try_import_texture(device, buf, on_drop, usage, implicit_modifier)
+        .map_err(|err| DmatexImportError::new(err, info))
+}
+
+fn try_import_texture(
+    device: &RenderDevice,
+    buf: Dmatex,
+    on_drop: DropCallback,
+    usage: DmatexUsage,
+    implicit_modifier: ImplicitModifierPolicy,
+) -> Result<ImportedTexture, PlaneError> {
+    validate_dmatex(&buf)?;
+    let vulkan_format = get_vulkan_format(&buf)?;
+    let convert_params = get_convert_params(&buf, vulkan_format);
+    if usage.writes() && !convert_params.is_identity() {
+        return Err(ImportError::ConversionNotWritable.into());
+    }
+    let dmabufs = dup_dmabufs(&buf.planes);
+    if let Some(ycbcr_info) = vk_format_ycbcr_info(vulkan_format)
+        && !supports_multi_planar_import(device, &buf, vulkan_format)
+    {
+        debug!("importing dmatex planes as separate textures");
+        return import_texture_planes(
+            device,
+            buf,
+            on_drop,
+            usage,
+            ycbcr_info,
+            convert_params,
+            implicit_modifier,
+        )
+        .map(|tex| ImportedTexture { dmabufs, ..tex });
+    }
+    let wgpu_desc = get_imported_descriptor(&buf, usage)?;
+    // wgpu reports textures of formats needing a disabled feature to the uncaptured error handler,
+    // which panics by default
+    if !device
+        .features()
+        .contains(wgpu_desc.format.required_features())
+    {
+        return Err(ImportError::WgpuIncompatibleFormat.into());
+    }
+    if wgpu_desc.usage.contains(TextureUsages::STORAGE_BINDING)
+        && !wgpu_desc
+            .format
+            .guaranteed_format_features(device.features())
+            .allowed_usages
+            .contains(TextureUsages::STORAGE_BINDING)
+    {
+        return Err(ImportError::StorageUnsupported.into());
+    }
+    let texture = import_vulkan_texture(
+        device,
+        buf.planes,
+        vulkan_format,
+        &wgpu_desc,
+        usage,
+        implicit_modifier,
+        on_drop,
+    )?;
+    let plane_views = vk_format_ycbcr_info(vulkan_format)
+        .map(|info| {
+            info.planes
+                .iter()
+                .zip([
+                    wgpu::TextureAspect::Plane0,
+                    wgpu::TextureAspect::Plane1,
+                    wgpu::TextureAspect::Plane2,
+                ])
+                .map(|(format, aspect)| {
+                    Ok(texture.create_view(&TextureViewDescriptor {
+                        label: None,
+                        format: Some(
+                            vulkan_to_wgpu(*format).ok_or(ImportError::WgpuIncompatibleFormat)?,
+                        ),
+                        dimension: Some(wgpu::TextureViewDimension::D2),
+                        usage: Some(TextureUsages::TEXTURE_BINDING),
+                        aspect,
+                        base_mip_level: 0,
+                        mip_level_count: Some(1),
+                        base_array_layer: 0,
+                        array_layer_count: Some(1),
+                    }))
+                })
+                .collect::<Result<Vec<_>, ImportError>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+    if let Some(plane_view) = plane_views.first() {
+        return Ok(ImportedTexture {
+            texture_view: plane_view.clone(),
+            texture,
+            plane_views,
+            plane_textures: Vec::new(),
+            convert_params,
+            converted: None,
+            usage,
+            acquired: default(),
+            dmabufs,
+            acquire_point: None,
+            info: None,
+            _on_release: None,
+        });
+    }
+    let texture_view = create_texture_view(&texture);
+    Ok(ImportedTexture {
+        texture,
+        texture_view,
+        plane_views,
+        plane_textures: Vec::new(),
+        convert_params,
+        converted: None,
+        usage,
+        acquired: default(),
+        dmabufs,
+        acquire_point: None,
+        info: None,
+        _on_release: None,
+    })
+}
+
+/// dups the distinct dmabufs of the planes, planes often share a single dmabuf
+fn dup_dmabufs(planes: &[DmatexPlane]) -> Arc<[OwnedFd]> {
+    let mut ids = Vec::with_capacity(planes.len());
+    planes
+        .iter()
+        .filter(|plane| {
+            let Ok(id) = DmabufId::new(&plane.dmabuf_fd) else {
+                return true;
+            };
+            let duplicate = ids.contains(&id);
+            ids.push(id);
+            !duplicate
+        })
+        .filter_map(|plane| {
+            plane
+                .dmabuf_fd
+                .as_fd()
+                .try_clone_to_owned()
+                .inspect_err(|err| warn!("unable to dup dmabuf: {err}"))
+                .ok()
+        })
+        .collect()
+}
+
+/// checks if the multi-planar Y′CbCr dmatex can be imported as a single texture, if not each plane
+/// has to be imported as a separate texture
+fn supports_multi_planar_import(
+    device: &RenderDevice,
+    buf: &Dmatex,
+    vulkan_format: vk::Format,
+) -> bool {
+    // wgpu can only create views of the planes of its own multi-planar formats
+    if !vulkan_to_wgpu(vulkan_format).is_some_and(|format| {
+        format.is_multi_planar_format() && device.features().contains(format.required_features())
+    }) {
+        return false;
+    }
+    unsafe {
+        device.wgpu_device().as_hal::<Vulkan, _, _>(|dev| {
+            let Some(dev) = dev else {
+                return false;
+            };
+            if !has_drm_format_modifier_support(dev) {
+                return false;
+            }
+            let (_format_properties, drm_format_properties) = get_drm_modifiers(
+                dev.shared_instance().raw_instance(),
+                dev.raw_physical_device(),
+                vulkan_format,
+            );
+            buf.planes.iter().all(|plane| {
+                drm_format_properties
+                    .iter()
+                    .find(|v| v.drm_format_modifier == plane.modifier)
+                    .is_some_and(|v| {
+                        v.drm_format_modifier_tiling_features
+                            .contains(FormatFeatureFlags2::SAMPLED_IMAGE)
+                    })
+            })
+        })
+    }
+}
+
+/// fallback for Y′CbCr dmatexs that can't be imported as a multi-planar texture, every plane is
+/// imported as a single plane texture and converted to RGB in the convert pass
+fn import_texture_planes(
+    device: &RenderDevice,
+    buf: Dmatex,
+    on_drop: DropCallback,
+    usage: DmatexUsage,
+    ycbcr_info: YcbcrFormatInfo,
+    convert_params: ConvertParams,
+    implicit_modifier: ImplicitModifierPolicy,
+) -> Result<ImportedTexture, PlaneError> {
+    if buf.planes.len() != ycbcr_info.planes.len() {
+        return Err(ImportError::IncorrectNumberOfPlanes.into());
+    }
+    // only run the callback once all planes are dropped
+    let on_drop = Arc::new(on_drop);
+    let (subsampling_x, subsampling_y) = ycbcr_info.chroma_subsampling;
+    let mut textures = Vec::with_capacity(buf.planes.len());
+    for (i, (plane, format)) in buf.planes.into_iter().zip(ycbcr_info.planes).enumerate() {
+        let res = match i {
+            0 => buf.res,
+            _ => Resolution {
+                x: buf.res.x.div_ceil(subsampling_x),
+                y: buf.res.y.div_ceil(subsampling_y),
+            },
+        };
+        let wgpu_desc = wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: res.x,
+                height: res.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: vulkan_to_wgpu(*format)
+                .filter(|format| device.features().contains(format.required_features()))
+                .ok_or(ImportError::WgpuIncompatibleFormat.in_plane(i))?,
+            usage: usage.texture_usage(),
+            view_formats: &[],
+        };
+        // the texture only has a single plane, so the error is caused by plane `i`
+        textures.push(
+            import_vulkan_texture(
+                device,
+                vec![plane],
+                *format,
+                &wgpu_desc,
+                usage,
+                implicit_modifier,
+                on_drop.clone(),
+            )
+            .map_err(|err| PlaneError {
+                plane: Some(i),
+                ..err
+            })?,
+        );
+    }
+    let plane_views = textures.iter().map(create_texture_view).collect::<Vec<_>>();
+    let mut textures = textures.into_iter();
+    let (Some(texture), Some(texture_view)) = (textures.next(), plane_views.first().cloned())
+    else {
+        return Err(ImportError::NoPlanes.into());
+    };
+    Ok(ImportedTexture {
+        texture,
+        texture_view,
+        plane_views,
+        plane_textures: textures.collect(),
+        convert_params,
+        converted: None,
+        usage,
+        acquired: default(),
+        dmabufs: default(),
+        acquire_point: None,
+        info: None,
+        _on_release: None,
+    })
+}
+
+fn create_texture_view(texture: &Texture) -> TextureView {
+    texture.create_view(&TextureViewDescriptor {
+        label: None,
+        format: Some(texture.format()),
+        dimension: Some(wgpu::TextureViewDimension::D2),
+        usage: Some(texture.usage()),
+        aspect: wgpu::TextureAspect::All,
+        base_mip_level: 0,
+        mip_level_count: Some(texture.mip_level_count()),
+        base_array_layer: 0,
+        array_layer_count: Some(texture.depth_or_array_layers()),
+    })
+}
+
+/// usage and create flags of the vulkan image a dmabuf with the given format gets imported as
+/// true if the planes are in different dmabufs, which requires an image with disjoint planes.
+/// Planes sharing a dmabuf have fds of the same inode
+pub(crate) fn planes_in_separate_dmabufs(planes: &[DmatexPlane]) -> Result<bool, PlaneError> {
+    let mut first = None;
+    for (i, plane) in planes.iter().enumerate() {
+        let id = DmabufId::new(&plane.dmabuf_fd).map_err(|err| {
+            ImportError::FdQueryFailed(err.raw_os_error().unwrap_or(libc::EIO)).in_plane(i)
+        })?;
+        match first {
+            None => first = Some(id),
+            Some(first) if first != id => return Ok(true),
+            Some(_) => {}
+        }
+    }
+    Ok(false)
+}
+
+pub(crate) fn get_image_flags(
+    vulkan_format: vk::Format,
+    disjoint: bool,
+    usage: DmatexUsage,
+) -> (vk::ImageUsageFlags, vk::ImageCreateFlags) {
+    let ycbcr_info = vk_format_ycbcr_info(vulkan_format);
+    let usage_flags = usage.image_usage();
+    let mut create_flags = match disjoint {
+        true => vk::ImageCreateFlags::DISJOINT,
+        false => vk::ImageCreateFlags::empty(),
+    };
+    if ycbcr_info.is_some() {
+        // needed to create views of the individual planes
+        create_flags |= vk::ImageCreateFlags::MUTABLE_FORMAT;
+    }
+    (usage_flags, create_flags)
+}
+
+/// checks if VK_EXT_image_drm_format_modifier is enabled on the device, without it only LINEAR
+/// dmabufs can be imported
+pub(crate) fn has_drm_format_modifier_support(dev: &<Vulkan as wgpu::hal::Api>::Device) -> bool {
+    unsafe {
+        dev.shared_instance()
+            .raw_instance()
+            .get_device_proc_addr(
+                dev.raw_device().handle(),
+                c"vkGetImageDrmFormatModifierPropertiesEXT".as_ptr(),
+            )
+            .is_some()
+    }
+}
+
+/// properties of a LINEAR image imported without VK_EXT_image_drm_format_modifier
+pub(crate) fn get_linear_image_info(
+    instance: &ash::Instance,
+    physical_device: vk::PhysicalDevice,
+    vulkan_format: vk::Format,
+    usage: DmatexUsage,
+) -> Option<ImageFormatProperties> {
+    let (usage_flags, create_flags) = get_image_flags(vulkan_format, false, usage);
+    get_external_image_info(
+        instance,
+        physical_device,
+        vulkan_format,
+        vk::ImageType::TYPE_2D,
+        usage_flags,
+        create_flags,
+        vk::ImageTiling::LINEAR,
     )
-    .ok_or(ImportError::VulkanIncompatibleFormat)?;
-    let vulkan_format = buf
-        .srgb
-        .then(|| vk_format_to_srgb(vulkan_format))
-        .flatten()
-        .unwrap_or(vulkan_format);
-    let wgpu_desc = get_imported_descriptor(&buf)?;
+}
+
+/// how a dmabuf with an implicit modifier ([`DRM_FORMAT_MOD_INVALID`]) gets imported
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum ImplicitModifier {
+    /// the buffer is assumed to be LINEAR and imported with the LINEAR modifier, so the offsets and
+    /// strides of the planes are respected
+    Linear(ImageFormatProperties),
+    /// imported without a drm modifier, the driver derives the layout of the buffer
+    DriverTiling(vk::ImageTiling, ImageFormatProperties),
+}
+
+impl ImplicitModifier {
+    pub(crate) fn image_format_properties(&self) -> ImageFormatProperties {
+        match self {
+            ImplicitModifier::Linear(properties) => *properties,
+            ImplicitModifier::DriverTiling(_, properties) => *properties,
+        }
+    }
+}
+
+/// picks how dmabufs with an implicit modifier are imported: LINEAR if the driver supports the
+/// LINEAR modifier for the format, or non drm modifier tiling if the driver can import dmabufs
+/// with it, in the order of the `policy`
+pub(crate) fn resolve_implicit_modifier(
+    instance: &ash::Instance,
+    physical_device: vk::PhysicalDevice,
+    vulkan_format: vk::Format,
+    drm_format_properties: &[vk::DrmFormatModifierProperties2EXT],
+    usage: DmatexUsage,
+    policy: ImplicitModifierPolicy,
+) -> Option<ImplicitModifier> {
+    let image_type = vk::ImageType::TYPE_2D;
+    let (usage_flags, create_flags) = get_image_flags(vulkan_format, false, usage);
+    let linear = || {
+        drm_format_properties
+            .iter()
+            .find(|v| v.drm_format_modifier == DRM_FORMAT_MOD_LINEAR)
+            .filter(|v| {
+                v.drm_format_modifier_tiling_features
+                    .contains(usage.format_features())
+            })
+            .and_then(|_| {
+                get_drm_image_modifier_info(
+                    instance,
+                    physical_device,
+                    vulkan_format,
+                    image_type,
+                    usage_flags,
+                    create_flags,
+                    DRM_FORMAT_MOD_LINEAR,
+                )
+            })
+            .map(ImplicitModifier::Linear)
+    };
+    // without a modifier the memory of a multi-planar image is bound at a single offset, so the
+    // offsets of the other planes can't be respected
+    let driver_tiling = || {
+        let tiling = vk::ImageTiling::OPTIMAL;
+        vk_format_ycbcr_info(vulkan_format)
+            .is_none()
+            .then(|| {
+                get_external_image_info(
+                    instance,
+                    physical_device,
+                    vulkan_format,
+                    image_type,
+                    usage_flags,
+                    create_flags,
+                    tiling,
+                )
+            })
+            .flatten()
+            .map(|properties| ImplicitModifier::DriverTiling(tiling, properties))
+    };
+    match policy {
+        ImplicitModifierPolicy::PreferLinear => linear().or_else(driver_tiling),
+        ImplicitModifierPolicy::PreferDriverTiling => driver_tiling().or_else(linear),
+    }
+}
+
+/// the aspects of the memory planes of a drm modifier image
+const MEMORY_PLANE_ASPECTS: [vk::ImageAspectFlags; 4] = [
+    vk::ImageAspectFlags::MEMORY_PLANE_0_EXT,
+    vk::ImageAspectFlags::MEMORY_PLANE_1_EXT,
+    vk::ImageAspectFlags::MEMORY_PLANE_2_EXT,
+    vk::ImageAspectFlags::MEMORY_PLANE_3_EXT,
+];
+
+/// imports the planes of a dmabuf as a single vulkan image with the size of `wgpu_desc` and wraps
+/// it in a wgpu texture, `on_drop` is dropped once the texture is destroyed
+fn import_vulkan_texture(
+    device: &RenderDevice,
+    mut planes: Vec<DmatexPlane>,
+    vulkan_format: vk::Format,
+    wgpu_desc: &wgpu::TextureDescriptor<'_>,
+    usage: DmatexUsage,
+    implicit_modifier: ImplicitModifierPolicy,
+    on_drop: impl Send + Sync + 'static,
+) -> Result<Texture, PlaneError> {
+    let res = Resolution {
+        x: wgpu_desc.size.width,
+        y: wgpu_desc.size.height,
+    };
+    let ycbcr_info = vk_format_ycbcr_info(vulkan_format);
     let (image, mem) = unsafe {
         device
             .wgpu_device()
-            .as_hal::<Vulkan, _, _>(|dev| -> Result<_, ImportError> {
+            .as_hal::<Vulkan, _, _>(|dev| -> Result<_, PlaneError> {
                 let dev = dev.ok_or(ImportError::NotVulkan)?;
-                let (_format_properties, drm_format_properties) = get_drm_modifiers(
-                    dev.shared_instance().raw_instance(),
-                    dev.raw_physical_device(),
-                    vulkan_format,
-                );
-                let mut disjoint = false;
-                for plane in buf.planes.iter() {
+                let modifier_support = has_drm_format_modifier_support(dev);
+                let drm_format_properties = match modifier_support {
+                    true => {
+                        get_drm_modifiers(
+                            dev.shared_instance().raw_instance(),
+                            dev.raw_physical_device(),
+                            vulkan_format,
+                        )
+                        .1
+                    }
+                    false => Vec::new(),
+                };
+                let implicit_modifier = match planes
+                    .iter()
+                    .filter(|p| p.modifier == DRM_FORMAT_MOD_INVALID)
+                    .count()
+                {
+                    _ if !modifier_support => None,
+                    0 => None,
+                    n if n == planes.len() => Some(
+                        resolve_implicit_modifier(
+                            dev.shared_instance().raw_instance(),
+                            dev.raw_physical_device(),
+                            vulkan_format,
+                            &drm_format_properties,
+                            usage,
+                            implicit_modifier,
+                        )
+                        .ok_or(ImportError::ImplicitModifierUnsupported)?,
+                    ),
+                    // the planes of a dmabuf can't mix implicit and explicit modifiers
+                    _ => return Err(ImportError::ModifierInvalid.into()),
+                };
+                // limits of the image for the format, modifier and usage, one entry per queried
+                // modifier
+                let mut format_properties = Vec::with_capacity(planes.len());
+                let driver_tiling = match implicit_modifier {
+                    Some(ImplicitModifier::Linear(_)) => {
+                        debug!("importing dmatex with implicit modifier as LINEAR");
+                        for plane in planes.iter_mut() {
+                            plane.modifier = DRM_FORMAT_MOD_LINEAR;
+                        }
+                        None
+                    }
+                    Some(ImplicitModifier::DriverTiling(tiling, properties)) => {
+                        debug!("importing dmatex with implicit modifier as {tiling:?}");
+                        format_properties.push(properties);
+                        Some(tiling)
+                    }
+                    None if !modifier_support => {
+                        // without drm modifiers only LINEAR buffers can be imported, with the
+                        // layout the driver picks for LINEAR images
+                        if planes.iter().any(|p| {
+                            p.modifier != DRM_FORMAT_MOD_LINEAR
+                                && p.modifier != DRM_FORMAT_MOD_INVALID
+                        }) {
+                            return Err(ImportError::ModifiersUnsupported.into());
+                        }
+                        let (usage_flags, create_flags) =
+                            get_image_flags(vulkan_format, false, usage);
+                        format_properties.push(
+                            get_linear_image_info(
+                                dev.shared_instance().raw_instance(),
+                                dev.raw_physical_device(),
+                                vulkan_format,
+                                usage,
+                            )
+                            .ok_or(ImportError::ImageUnsupported {
+                                usage: usage_flags,
+                                flags: create_flags,
+                            })?,
+                        );
+                        Some(vk::ImageTiling::LINEAR)
+                    }
+                    None => None,
+                };
+                if driver_tiling.is_some() && planes.len() != 1 {
+                    return Err(ImportError::IncorrectNumberOfPlanes.into());
+                }
+                // planes in the same dmabuf are bound as one memory, even if the modifier
+                // supports disjoint planes
+                let disjoint = planes_in_separate_dmabufs(&planes)?;
+                for (i, plane) in planes.iter().enumerate() {
+                    if driver_tiling.is_some() {
+                        break;
+                    }
                     let used_modifier = drm_format_properties
                         .iter()
                         .find(|v| v.drm_format_modifier == plane.modifier)
-                        .ok_or(ImportError::ModifierInvalid)?;
-                    disjoint |= used_modifier
-                        .drm_format_modifier_tiling_features
-                        .contains(FormatFeatureFlags2::DISJOINT_KHR);
+                        .ok_or(ImportError::ModifierInvalid.in_plane(i))?;
+                    if disjoint
+                        && !used_modifier
+                            .drm_format_modifier_tiling_features
+                            .contains(FormatFeatureFlags2::DISJOINT_KHR)
+                    {
+                        return Err(ImportError::NonDisjointPlanesInSeparateBuffers.in_plane(i));
+                    }
+                    let missing = usage.format_features()
+                        & !used_modifier.drm_format_modifier_tiling_features;
+                    if !missing.is_empty() {
+                        return Err(ImportError::MissingFeatures(missing).in_plane(i));
+                    }
+                    if ycbcr_info.is_some()
+                        && used_modifier.drm_format_modifier_plane_count as usize != planes.len()
+                    {
+                        return Err(ImportError::IncorrectNumberOfPlanes.into());
+                    }
                 }
                 let image_type = vk::ImageType::TYPE_2D;
-                let usage_flags = vk::ImageUsageFlags::COLOR_ATTACHMENT
-                    | vk::ImageUsageFlags::SAMPLED
-                    | vk::ImageUsageFlags::TRANSFER_SRC
-                    | vk::ImageUsageFlags::TRANSFER_DST;
-                let create_flags = match disjoint {
-                    true => vk::ImageCreateFlags::DISJOINT,
-                    false => vk::ImageCreateFlags::empty(),
-                };
-                for plane in buf.planes.iter() {
-                    let _format_info = get_drm_image_modifier_info(
-                        dev.shared_instance().raw_instance(),
-                        dev.raw_physical_device(),
-                        vulkan_format,
-                        image_type,
-                        usage_flags,
-                        create_flags,
-                        plane.modifier,
-                    )
-                    .ok_or(ImportError::ModifierInvalid)?;
+                let (usage_flags, create_flags) = get_image_flags(vulkan_format, disjoint, usage);
+                for (i, plane) in planes.iter().enumerate() {
+                    if driver_tiling.is_some() {
+                        break;
+                    }
+                    format_properties.push(
+                        get_drm_image_modifier_info(
+                            dev.shared_instance().raw_instance(),
+                            dev.raw_physical_device(),
+                            vulkan_format,
+                            image_type,
+                            usage_flags,
+                            create_flags,
+                            plane.modifier,
+                        )
+                        .ok_or(
+                            ImportError::ImageUnsupported {
+                                usage: usage_flags,
+                                flags: create_flags,
+                            }
+                            .in_plane(i),
+                        )?,
+                    );
+                }
+                if format_properties
+                    .iter()
+                    .any(|v| res.x > v.max_extent.width || res.y > v.max_extent.height)
+                {
+                    return Err(ImportError::ResolutionTooLarge.into());
                 }
-                let plane_layouts = buf
-                    .planes
+                let plane_layouts = planes
                     .iter()
                     .map(|p| SubresourceLayout {
                         offset: p.offset as _,
@@ -558,19 +2684,23 @@ pub fn import_texture(
                         size: 0,
                     })
                     .collect::<Vec<_>>();
-                let modifiers = buf.planes.iter().map(|p| p.modifier).collect::<Vec<_>>();
-                if buf.planes.is_empty() {
-                    return Err(ImportError::NoPlanes);
+                let modifiers = planes.iter().map(|p| p.modifier).collect::<Vec<_>>();
+                if planes.is_empty() {
+                    return Err(ImportError::NoPlanes.into());
                 }
-                let mut drm_explicit_create_info = (buf.planes.len() == 1).then(|| {
-                    vk::ImageDrmFormatModifierExplicitCreateInfoEXT::default()
-                        .drm_format_modifier(modifiers[0])
-                        .plane_layouts(&plane_layouts)
-                });
-                let mut drm_list_create_info = (buf.planes.len() > 1).then(|| {
-                    vk::ImageDrmFormatModifierListCreateInfoEXT::default()
-                        .drm_format_modifiers(&modifiers)
-                });
+                // the planes of a multi-planar format always share one modifier
+                let explicit_layout = planes.len() == 1 || ycbcr_info.is_some();
+                let mut drm_explicit_create_info = (driver_tiling.is_none() && explicit_layout)
+                    .then(|| {
+                        vk::ImageDrmFormatModifierExplicitCreateInfoEXT::default()
+                            .drm_format_modifier(modifiers[0])
+                            .plane_layouts(&plane_layouts)
+                    });
+                let mut drm_list_create_info =
+                    (driver_tiling.is_none() && !explicit_layout).then(|| {
+                        vk::ImageDrmFormatModifierListCreateInfoEXT::default()
+                            .drm_format_modifiers(&modifiers)
+                    });
                 let mut external_memory_info = vk::ExternalMemoryImageCreateInfo::default()
                     .handle_types(vk::ExternalMemoryHandleTypeFlags::DMA_BUF_EXT);
 
@@ -581,15 +2711,15 @@ pub fn import_texture(
                     .flags(create_flags)
                     .format(vulkan_format)
                     .extent(vk::Extent3D {
-                        width: buf.res.x,
-                        height: buf.res.y,
+                        width: res.x,
+                        height: res.y,
                         depth: 1,
                     })
                     .samples(vk::SampleCountFlags::TYPE_1)
                     .array_layers(1)
                     .mip_levels(1)
                     .initial_layout(vk::ImageLayout::UNDEFINED)
-                    .tiling(vk::ImageTiling::DRM_FORMAT_MODIFIER_EXT)
+                    .tiling(driver_tiling.unwrap_or(vk::ImageTiling::DRM_FORMAT_MODIFIER_EXT))
                     .push_next(&mut external_memory_info);
                 if let Some(info) = drm_explicit_create_info.as_mut() {
                     image_create_info = image_create_info.push_next(info);
@@ -601,6 +2731,49 @@ pub fn import_texture(
                     .raw_device()
                     .create_image(&image_create_info, None)
                     .map_err(ImportError::VulkanImageCreationFailed)?;
+                // the driver has to use the layout of the producer, otherwise the planes would be
+                // read from the wrong part of the dmabuf
+                if driver_tiling.is_none() {
+                    for (i, (plane, aspect_flags)) in
+                        planes.iter().zip(MEMORY_PLANE_ASPECTS).enumerate()
+                    {
+                        let layout = dev.raw_device().get_image_subresource_layout(
+                            image,
+                            vk::ImageSubresource::default().aspect_mask(aspect_flags),
+                        );
+                        if layout.offset != plane.offset as u64
+                            || layout.row_pitch != plane.stride as u64
+                        {
+                            dev.raw_device().destroy_image(image, None);
+                            return Err(ImportError::PlaneLayoutMismatch.in_plane(i));
+                        }
+                    }
+                }
+                // without a drm modifier the offset of the plane is the offset the memory is
+                // bound at, and the stride has to match the row pitch of the driver
+                let bind_offset = match driver_tiling {
+                    Some(tiling) => {
+                        let plane = planes.first().ok_or(ImportError::NoPlanes)?;
+                        let alignment = dev
+                            .raw_device()
+                            .get_image_memory_requirements(image)
+                            .alignment;
+                        let layout = dev.raw_device().get_image_subresource_layout(
+                            image,
+                            vk::ImageSubresource::default()
+                                .aspect_mask(vk::ImageAspectFlags::COLOR),
+                        );
+                        if !(plane.offset as u64).is_multiple_of(alignment.max(1))
+                            || (tiling == vk::ImageTiling::LINEAR
+                                && layout.row_pitch != plane.stride as u64)
+                        {
+                            dev.raw_device().destroy_image(image, None);
+                            return Err(ImportError::PlaneLayoutMismatch.in_plane(0));
+                        }
+                        plane.offset as u64
+                    }
+                    None => 0,
+                };
 
                 let mem_properties = {
                     dev.shared_instance()
@@ -636,15 +2809,11 @@ pub fn import_texture(
                 let mut plane_mems = Vec::with_capacity(4);
                 match disjoint {
                     true => {
-                        for (i, v) in buf.planes.into_iter().enumerate() {
+                        for (i, v) in planes.into_iter().enumerate() {
                             let fd = OwnedFd::from(v.dmabuf_fd);
-                            let aspect_flags = match i {
-                                0 => vk::ImageAspectFlags::MEMORY_PLANE_0_EXT,
-                                1 => vk::ImageAspectFlags::MEMORY_PLANE_1_EXT,
-                                2 => vk::ImageAspectFlags::MEMORY_PLANE_2_EXT,
-                                3 => vk::ImageAspectFlags::MEMORY_PLANE_3_EXT,
-                                _ => return Err(ImportError::IncorrectNumberOfPlanes),
-                            };
+                            let aspect_flags = *MEMORY_PLANE_ASPECTS
+                                .get(i)
+                                .ok_or(ImportError::IncorrectNumberOfPlanes)?;
                             let mut dedicated_req = MemoryDedicatedRequirements::default();
                             let mut plane_req_info = ImagePlaneMemoryRequirementsInfo::default()
                                 .plane_aspect(aspect_flags);
@@ -679,7 +2848,9 @@ pub fn import_texture(
                                 .raw_device()
                                 .allocate_memory(&alloc_info, None)
                                 .inspect_err(|_| dev.raw_device().destroy_image(image, None))
-                                .map_err(ImportError::VulkanMemoryAllocFailed)?;
+                                .map_err(|err| {
+                                    ImportError::VulkanMemoryAllocFailed(err).in_plane(i)
+                                })?;
                             plane_mems.push((
                                 mem,
                                 Some(
@@ -691,7 +2862,7 @@ pub fn import_texture(
                     }
                     false => {
                         let fd = OwnedFd::from(
-                            buf.planes
+                            planes
                                 .into_iter()
                                 .next()
                                 .ok_or(ImportError::NoPlanes)?
@@ -703,7 +2874,7 @@ pub fn import_texture(
                         let mem_req_info = vk::ImageMemoryRequirementsInfo2::default().image(image);
                         dev.raw_device()
                             .get_image_memory_requirements2(&mem_req_info, &mut mem_reqs);
-                        let size = mem_reqs.memory_requirements.size;
+                        let size = bind_offset + mem_reqs.memory_requirements.size;
 
                         let needs_dedicated = dedicated_req.requires_dedicated_allocation != 0;
 
@@ -733,7 +2904,10 @@ pub fn import_texture(
                             .image(image)
                             .memory(*mem)
                             .push_next(info),
-                        None => vk::BindImageMemoryInfo::default().image(image).memory(*mem),
+                        None => vk::BindImageMemoryInfo::default()
+                            .image(image)
+                            .memory(*mem)
+                            .memory_offset(bind_offset),
                     })
                     .collect::<Vec<_>>();
                 dev.raw_device()
@@ -746,15 +2920,15 @@ pub fn import_texture(
     let descriptor = TextureDescriptor {
         label: None,
         size: wgpu::Extent3d {
-            width: buf.res.x,
-            height: buf.res.y,
+            width: res.x,
+            height: res.y,
             depth_or_array_layers: 1,
         },
         mip_level_count: 1,
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
-        format: vulkan_to_wgpu(vulkan_format).ok_or(ImportError::WgpuIncompatibleFormat)?,
-        usage: TextureUses::COLOR_TARGET | TextureUses::PRESENT,
+        format: wgpu_desc.format,
+        usage: usage.texture_uses(),
         memory_flags: MemoryFlags::empty(),
         view_formats: vec![],
     };
@@ -781,23 +2955,67 @@ pub fn import_texture(
     let wgpu_texture = unsafe {
         device
             .wgpu_device()
-            .create_texture_from_hal::<Vulkan>(texture, &wgpu_desc)
+            .create_texture_from_hal::<Vulkan>(texture, wgpu_desc)
     };
-    let texture = Texture::from(wgpu_texture);
-    let texture_view = texture.create_view(&TextureViewDescriptor {
-        label: None,
-        format: Some(texture.format()),
-        dimension: Some(wgpu::TextureViewDimension::D2),
-        usage: Some(texture.usage()),
-        aspect: wgpu::TextureAspect::All,
-        base_mip_level: 0,
-        mip_level_count: Some(texture.mip_level_count()),
-        base_array_layer: 0,
-        array_layer_count: Some(texture.depth_or_array_layers()),
-    });
-    Ok(ImportedTexture {
-        texture,
-        texture_view,
-        _usage: usage,
-    })
+    Ok(Texture::from(wgpu_texture))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::fd::FromRawFd as _;
+
+    use super::*;
+
+    fn memfd() -> OwnedFd {
+        let fd = unsafe { libc::memfd_create(c"dmatex".as_ptr(), libc::MFD_CLOEXEC) };
+        assert!(fd >= 0, "{}", io::Error::last_os_error());
+        unsafe { OwnedFd::from_raw_fd(fd) }
+    }
+
+    fn plane(fd: OwnedFd, offset: u32) -> DmatexPlane {
+        DmatexPlane {
+            dmabuf_fd: fd.into(),
+            modifier: DRM_FORMAT_MOD_LINEAR,
+            offset,
+            stride: 64,
+        }
+    }
+
+    /// the Y′ and CbCr planes of a 64x64 NV12 dmatex, in one or two dmabufs
+    #[expect(clippy::unwrap_used)]
+    fn nv12_planes(single_fd: bool) -> Vec<DmatexPlane> {
+        let luma = memfd();
+        let chroma = match single_fd {
+            true => luma.try_clone().unwrap(),
+            false => memfd(),
+        };
+        let chroma_offset = match single_fd {
+            true => 64 * 64,
+            false => 0,
+        };
+        vec![plane(luma, 0), plane(chroma, chroma_offset)]
+    }
+
+    #[test]
+    fn nv12_planes_in_separate_dmabufs() {
+        assert_eq!(planes_in_separate_dmabufs(&nv12_planes(true)), Ok(false));
+        assert_eq!(planes_in_separate_dmabufs(&nv12_planes(false)), Ok(true));
+        assert_eq!(planes_in_separate_dmabufs(&[]), Ok(false));
+    }
+
+    #[test]
+    fn nv12_image_flags() {
+        let format = vk::Format::G8_B8R8_2PLANE_420_UNORM;
+        for single_fd in [true, false] {
+            let disjoint = planes_in_separate_dmabufs(&nv12_planes(single_fd)).unwrap_or(false);
+            let (usage_flags, create_flags) =
+                get_image_flags(format, disjoint, DmatexUsage::Sampling);
+            assert_eq!(usage_flags, DmatexUsage::Sampling.image_usage());
+            assert!(create_flags.contains(vk::ImageCreateFlags::MUTABLE_FORMAT));
+            assert_eq!(
+                create_flags.contains(vk::ImageCreateFlags::DISJOINT),
+                !single_fd
+            );
+        }
+    }
 }
@@ -0,0 +1,235 @@
+use std::{
+    io,
+    mem::MaybeUninit,
+    os::fd::{AsFd, AsRawFd as _},
+};
+
+use crate::{
+    dmatex::{
+        ChromaSiting, ColorPrimaries, Dmatex, DmatexTransform, Resolution, TransferFunction,
+        YcbcrEncoding, YcbcrRange,
+    },
+    import::{DmatexUsage, ImportedTexture},
+};
+
+/// identifies a dmabuf across fds, dup'ed and re-sent fds of the same buffer share the inode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct DmabufId {
+    dev: libc::dev_t,
+    ino: libc::ino_t,
+}
+
+impl DmabufId {
+    pub(crate) fn new(fd: impl AsFd) -> io::Result<DmabufId> {
+        let mut stat = MaybeUninit::<libc::stat>::uninit();
+        if unsafe { libc::fstat(fd.as_fd().as_raw_fd(), stat.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let stat = unsafe { stat.assume_init() };
+        Ok(DmabufId {
+            dev: stat.st_dev,
+            ino: stat.st_ino,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct PlaneKey {
+    id: DmabufId,
+    modifier: u64,
+    offset: u32,
+    stride: i32,
+}
+
+/// everything that influences how a dmatex gets imported
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ImportCacheKey {
+    planes: Vec<PlaneKey>,
+    res: Resolution,
+    format: u32,
+    transform: DmatexTransform,
+    srgb: bool,
+    ycbcr_encoding: YcbcrEncoding,
+    ycbcr_range: YcbcrRange,
+    chroma_siting: ChromaSiting,
+    color_primaries: ColorPrimaries,
+    transfer_function: TransferFunction,
+    usage: DmatexUsage,
+}
+
+impl ImportCacheKey {
+    pub(crate) fn new(buf: &Dmatex, usage: DmatexUsage) -> io::Result<ImportCacheKey> {
+        Ok(ImportCacheKey {
+            planes: buf
+                .planes
+                .iter()
+                .map(|plane| {
+                    Ok(PlaneKey {
+                        id: DmabufId::new(&plane.dmabuf_fd)?,
+                        modifier: plane.modifier,
+                        offset: plane.offset,
+                        stride: plane.stride,
+                    })
+                })
+                .collect::<io::Result<_>>()?,
+            res: buf.res,
+            format: buf.format,
+            transform: buf.transform,
+            srgb: buf.srgb,
+            ycbcr_encoding: buf.ycbcr_encoding,
+            ycbcr_range: buf.ycbcr_range,
+            chroma_siting: buf.chroma_siting,
+            color_primaries: buf.color_primaries,
+            transfer_function: buf.transfer_function,
+            usage,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct CachedImport<T> {
+    key: ImportCacheKey,
+    texture: T,
+    last_used: u64,
+}
+
+/// imports of recently seen dmabufs, so producers cycling through a pool of buffers don't cause a
+/// new import every frame. The cached textures keep the dmabufs alive, so the inodes can't be
+/// reused by other buffers while cached
+#[derive(Debug)]
+pub(crate) struct ImportCache<T = ImportedTexture> {
+    frame: u64,
+    entries: Vec<CachedImport<T>>,
+}
+
+impl<T> Default for ImportCache<T> {
+    fn default() -> Self {
+        ImportCache {
+            frame: 0,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T: Clone> ImportCache<T> {
+    pub(crate) fn get(&mut self, key: &ImportCacheKey) -> Option<T> {
+        let entry = self.entries.iter_mut().find(|v| &v.key == key)?;
+        entry.last_used = self.frame;
+        Some(entry.texture.clone())
+    }
+    pub(crate) fn insert(&mut self, key: ImportCacheKey, texture: T) {
+        self.entries.retain(|v| v.key != key);
+        self.entries.push(CachedImport {
+            key,
+            texture,
+            last_used: self.frame,
+        });
+    }
+    /// advances the frame counter and drops the imports that weren't used in the last
+    /// `max_unused_frames` frames
+    pub(crate) fn evict_unused(&mut self, max_unused_frames: u32) {
+        self.frame += 1;
+        let frame = self.frame;
+        self.entries
+            .retain(|v| frame - v.last_used <= u64::from(max_unused_frames));
+    }
+    /// drops every import that uses the dmabuf
+    pub(crate) fn evict_dmabuf(&mut self, id: DmabufId) {
+        self.entries
+            .retain(|v| v.key.planes.iter().all(|plane| plane.id != id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::fd::{FromRawFd as _, OwnedFd};
+
+    use drm_fourcc::DrmFourcc;
+
+    use super::*;
+    use crate::dmatex::DmatexPlane;
+
+    fn dmatex(fd: OwnedFd) -> Dmatex {
+        Dmatex {
+            planes: vec![DmatexPlane {
+                dmabuf_fd: fd.into(),
+                modifier: 0,
+                offset: 0,
+                stride: 64 * 4,
+            }],
+            res: Resolution { x: 64, y: 64 },
+            format: DrmFourcc::Argb8888 as u32,
+            transform: Default::default(),
+            srgb: false,
+            ycbcr_encoding: Default::default(),
+            ycbcr_range: Default::default(),
+            chroma_siting: Default::default(),
+            color_primaries: Default::default(),
+            transfer_function: Default::default(),
+            sync: Default::default(),
+        }
+    }
+
+    fn memfd() -> OwnedFd {
+        let fd = unsafe { libc::memfd_create(c"dmatex".as_ptr(), libc::MFD_CLOEXEC) };
+        assert!(fd >= 0, "{}", io::Error::last_os_error());
+        unsafe { OwnedFd::from_raw_fd(fd) }
+    }
+
+    fn key(buf: &Dmatex) -> ImportCacheKey {
+        ImportCacheKey::new(buf, DmatexUsage::Sampling).unwrap()
+    }
+
+    #[test]
+    fn represented_dmabuf_hits() {
+        let fd = memfd();
+        let buf = dmatex(fd.try_clone().unwrap());
+        let mut cache = ImportCache::default();
+        cache.insert(key(&buf), 1);
+        // the producer sends the same buffer again with a new fd
+        let represented = dmatex(fd.try_clone().unwrap());
+        assert_eq!(cache.get(&key(&represented)), Some(1));
+        assert_eq!(cache.get(&key(&dmatex(memfd()))), None);
+        let rotated = Dmatex {
+            transform: DmatexTransform::Rotate90,
+            ..dmatex(fd)
+        };
+        assert_eq!(cache.get(&key(&rotated)), None);
+    }
+
+    #[test]
+    fn insert_replaces_entry() {
+        let buf = dmatex(memfd());
+        let mut cache = ImportCache::default();
+        cache.insert(key(&buf), 1);
+        cache.insert(key(&buf), 2);
+        assert_eq!(cache.get(&key(&buf)), Some(2));
+        assert_eq!(cache.entries.len(), 1);
+    }
+
+    #[test]
+    fn unused_entries_expire() {
+        let used = dmatex(memfd());
+        let unused = dmatex(memfd());
+        let mut cache = ImportCache::default();
+        cache.insert(key(&used), 1);
+        cache.insert(key(&unused), 2);
+        for _ in 0..3 {
+            cache.evict_unused(2);
+            assert_eq!(cache.get(&key(&used)), Some(1));
+        }
+        assert_eq!(cache.get(&key(&unused)), None);
+        assert_eq!(cache.get(&key(&used)), Some(1));
+    }
+
+    #[test]
+    fn evict_dmabuf() {
+        let fd = memfd();
+        let id = DmabufId::new(&fd).unwrap();
+        let buf = dmatex(fd);
+        let mut cache = ImportCache::default();
+        cache.insert(key(&buf), 1);
+        cache.evict_dmabuf(id);
+        assert_eq!(cache.get(&key(&buf)), None);
+    }
+}
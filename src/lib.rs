@@ -1,17 +1,40 @@
+//! import linux dmabufs into bevy images, see [`import::ImportedDmatexs`]
+//!
+//! [`dmatex::Dmatex`] is sent between processes over D-Bus, its wire format is only stable
+//! within a minor version. 0.3 replaced `flip_y` with `transform` and added the Y′CbCr, color and
+//! sync fields, so producers and importers have to be updated together, see CHANGELOG.md
+
 use std::ffi::CStr;
 
 pub mod wgpu_init;
 // pub mod export;
+pub mod convert;
+pub mod cpu_upload;
 pub mod dmatex;
 pub mod format_mapping;
+pub mod format_table;
 pub mod import;
+mod import_cache;
+mod sync_file;
+mod validate;
 
 pub fn required_device_extensions() -> Vec<&'static CStr> {
     vec![
-        ash::ext::image_drm_format_modifier::NAME,
         ash::ext::external_memory_dma_buf::NAME,
         ash::khr::external_memory_fd::NAME,
         ash::khr::external_memory::NAME,
         ash::khr::swapchain::NAME,
     ]
 }
+
+/// extensions that should be enabled when the device supports them, without
+/// VK_EXT_image_drm_format_modifier only LINEAR dmabufs can be imported and
+/// VK_EXT_queue_family_foreign is needed for [`import::ExternalQueueFamily::Foreign`] and
+/// VK_KHR_external_semaphore_fd for synchronizing with the implicit fences of dmabufs
+pub fn optional_device_extensions() -> Vec<&'static CStr> {
+    vec![
+        ash::ext::image_drm_format_modifier::NAME,
+        ash::ext::queue_family_foreign::NAME,
+        ash::khr::external_semaphore_fd::NAME,
+    ]
+}
@@ -0,0 +1,65 @@
+use std::{
+    io,
+    os::fd::{AsRawFd as _, BorrowedFd, FromRawFd as _, OwnedFd},
+};
+
+pub(crate) const DMA_BUF_SYNC_READ: u32 = 1 << 0;
+pub(crate) const DMA_BUF_SYNC_WRITE: u32 = 1 << 1;
+pub(crate) const DMA_BUF_SYNC_START: u64 = 0;
+pub(crate) const DMA_BUF_SYNC_END: u64 = 1 << 2;
+/// _IOW('b', 0, struct dma_buf_sync)
+const DMA_BUF_IOCTL_SYNC: u64 = 0x4008_6200;
+/// _IOWR('b', 2, struct dma_buf_export_sync_file)
+const DMA_BUF_IOCTL_EXPORT_SYNC_FILE: u64 = 0xc008_6202;
+/// _IOW('b', 3, struct dma_buf_import_sync_file)
+const DMA_BUF_IOCTL_IMPORT_SYNC_FILE: u64 = 0x4008_6203;
+
+/// struct dma_buf_export_sync_file and struct dma_buf_import_sync_file
+#[repr(C)]
+struct DmaBufSyncFile {
+    flags: u32,
+    fd: i32,
+}
+
+fn ioctl<T>(fd: BorrowedFd<'_>, request: u64, arg: &mut T) -> io::Result<()> {
+    loop {
+        let result = unsafe { libc::ioctl(fd.as_raw_fd(), request as _, arg as *mut T) };
+        if result == 0 {
+            return Ok(());
+        }
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::EINTR | libc::EAGAIN) => continue,
+            _ => return Err(err),
+        }
+    }
+}
+
+/// DMA_BUF_IOCTL_SYNC, `flags` is a combination of the DMA_BUF_SYNC_* flags
+pub(crate) fn sync(dmabuf: BorrowedFd<'_>, flags: u64) -> io::Result<()> {
+    let mut sync = flags;
+    ioctl(dmabuf, DMA_BUF_IOCTL_SYNC, &mut sync)
+}
+
+/// a sync_file that signals once the implicit fences of the dmabuf are done, with
+/// [`DMA_BUF_SYNC_READ`] that's every pending write, with [`DMA_BUF_SYNC_WRITE`] every pending
+/// access. Fails with ENOTTY on kernels older than 6.0
+pub(crate) fn export_sync_file(dmabuf: BorrowedFd<'_>, flags: u32) -> io::Result<OwnedFd> {
+    let mut arg = DmaBufSyncFile { flags, fd: -1 };
+    ioctl(dmabuf, DMA_BUF_IOCTL_EXPORT_SYNC_FILE, &mut arg)?;
+    Ok(unsafe { OwnedFd::from_raw_fd(arg.fd) })
+}
+
+/// adds the sync_file to the implicit fences of the dmabuf, as a read fence with
+/// [`DMA_BUF_SYNC_READ`] or as a write fence with [`DMA_BUF_SYNC_WRITE`]
+pub(crate) fn import_sync_file(
+    dmabuf: BorrowedFd<'_>,
+    sync_file: BorrowedFd<'_>,
+    flags: u32,
+) -> io::Result<()> {
+    let mut arg = DmaBufSyncFile {
+        flags,
+        fd: sync_file.as_raw_fd(),
+    };
+    ioctl(dmabuf, DMA_BUF_IOCTL_IMPORT_SYNC_FILE, &mut arg)
+}
@@ -0,0 +1,290 @@
+use std::{
+    io,
+    mem::MaybeUninit,
+    os::fd::{AsFd, AsRawFd as _},
+};
+
+use crate::{
+    dmatex::Dmatex,
+    format_mapping::vk_format_ycbcr_info,
+    import::{ImportError, PlaneError, get_vulkan_format},
+};
+
+/// f_type of the pseudo filesystem dmabuf fds live on
+const DMA_BUF_MAGIC: u64 = 0x444d_4142;
+/// larger than the max image dimension of any gpu, the device limits are checked on import
+pub(crate) const MAX_DMATEX_EXTENT: u32 = 1 << 15;
+/// a dmabuf has at most 4 memory planes
+pub(crate) const MAX_DMATEX_PLANES: usize = 4;
+
+/// checks a dmatex from an untrusted producer before anything is imported, so a malformed dmatex
+/// is rejected instead of making the driver read out of bounds of the dmabufs
+pub(crate) fn validate_dmatex(buf: &Dmatex) -> Result<(), PlaneError> {
+    if buf.planes.is_empty() {
+        return Err(ImportError::NoPlanes.into());
+    }
+    if buf.planes.len() > MAX_DMATEX_PLANES {
+        return Err(ImportError::IncorrectNumberOfPlanes.into());
+    }
+    if buf.res.x == 0
+        || buf.res.y == 0
+        || buf.res.x > MAX_DMATEX_EXTENT
+        || buf.res.y > MAX_DMATEX_EXTENT
+    {
+        return Err(ImportError::InvalidResolution.into());
+    }
+    let ycbcr_info = vk_format_ycbcr_info(get_vulkan_format(buf)?);
+    let format_planes = ycbcr_info.map_or(1, |info| info.planes.len());
+    if buf.planes.len() < format_planes {
+        return Err(ImportError::IncorrectNumberOfPlanes.into());
+    }
+    for (i, plane) in buf.planes.iter().enumerate() {
+        if !is_dmabuf(&plane.dmabuf_fd).map_err(|err| fd_query_failed(err).in_plane(i))? {
+            return Err(ImportError::NotADmabuf.in_plane(i));
+        }
+        let size = dmabuf_size(&plane.dmabuf_fd).map_err(|err| fd_query_failed(err).in_plane(i))?;
+        if plane.stride <= 0 || u64::from(plane.offset) >= size {
+            return Err(ImportError::InvalidPlaneLayout.in_plane(i));
+        }
+        // the planes past the ones of the format hold modifier specific data like compression
+        // metadata, their layout isn't known
+        if i >= format_planes {
+            continue;
+        }
+        let height = match (i, ycbcr_info) {
+            (1.., Some(info)) => buf.res.y.div_ceil(info.chroma_subsampling.1),
+            _ => buf.res.y,
+        };
+        let end = u64::from(plane.stride as u32)
+            .checked_mul(u64::from(height))
+            .and_then(|len| len.checked_add(u64::from(plane.offset)));
+        if end.is_none_or(|end| end > size) {
+            return Err(ImportError::DmabufTooSmall.in_plane(i));
+        }
+    }
+    Ok(())
+}
+
+fn fd_query_failed(err: io::Error) -> ImportError {
+    ImportError::FdQueryFailed(err.raw_os_error().unwrap_or(libc::EIO))
+}
+
+fn is_dmabuf(fd: impl AsFd) -> io::Result<bool> {
+    let mut stat = MaybeUninit::<libc::statfs>::uninit();
+    if unsafe { libc::fstatfs(fd.as_fd().as_raw_fd(), stat.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let stat = unsafe { stat.assume_init() };
+    Ok(stat.f_type as u64 == DMA_BUF_MAGIC)
+}
+
+/// dmabufs can't be resized, so the size seen here is the size the gpu sees
+fn dmabuf_size(fd: impl AsFd) -> io::Result<u64> {
+    let size = unsafe { libc::lseek(fd.as_fd().as_raw_fd(), 0, libc::SEEK_END) };
+    if size < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(size as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::fd::{FromRawFd as _, OwnedFd};
+
+    use drm_fourcc::DrmFourcc;
+
+    use super::*;
+    use crate::dmatex::{DmatexPlane, Resolution};
+
+    const STRIDE: u32 = 64 * 4;
+    /// _IOW('u', 0x42, struct udmabuf_create)
+    const UDMABUF_CREATE: libc::Ioctl = 0x4018_7542;
+    const UDMABUF_FLAGS_CLOEXEC: u32 = 0x01;
+
+    #[repr(C)]
+    struct UdmabufCreate {
+        memfd: u32,
+        flags: u32,
+        offset: u64,
+        size: u64,
+    }
+
+    /// a plane backed by a udmabuf of `size` bytes, None if /dev/udmabuf isn't available
+    fn udmabuf_plane(size: u64) -> Option<DmatexPlane> {
+        let dev = unsafe { libc::open(c"/dev/udmabuf".as_ptr(), libc::O_RDWR | libc::O_CLOEXEC) };
+        if dev < 0 {
+            eprintln!("skipping, /dev/udmabuf: {}", io::Error::last_os_error());
+            return None;
+        }
+        let dev = unsafe { OwnedFd::from_raw_fd(dev) };
+        let memfd = unsafe {
+            libc::memfd_create(
+                c"udmabuf".as_ptr(),
+                libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING,
+            )
+        };
+        assert!(memfd >= 0, "{}", io::Error::last_os_error());
+        let memfd = unsafe { OwnedFd::from_raw_fd(memfd) };
+        assert_eq!(
+            unsafe { libc::ftruncate(memfd.as_raw_fd(), size as i64) },
+            0
+        );
+        // udmabuf only accepts memfds that can't shrink
+        assert_eq!(
+            unsafe { libc::fcntl(memfd.as_raw_fd(), libc::F_ADD_SEALS, libc::F_SEAL_SHRINK) },
+            0
+        );
+        let create = UdmabufCreate {
+            memfd: memfd.as_raw_fd() as u32,
+            flags: UDMABUF_FLAGS_CLOEXEC,
+            offset: 0,
+            size,
+        };
+        let fd = unsafe { libc::ioctl(dev.as_raw_fd(), UDMABUF_CREATE, &create) };
+        assert!(fd >= 0, "{}", io::Error::last_os_error());
+        Some(DmatexPlane {
+            dmabuf_fd: unsafe { OwnedFd::from_raw_fd(fd) }.into(),
+            modifier: 0,
+            offset: 0,
+            stride: STRIDE as i32,
+        })
+    }
+
+    /// a plane backed by a memfd that is large enough for a 64x64 ARGB8888 dmatex
+    fn memfd_plane() -> DmatexPlane {
+        let fd = unsafe { libc::memfd_create(c"dmatex".as_ptr(), libc::MFD_CLOEXEC) };
+        assert!(fd >= 0, "{}", io::Error::last_os_error());
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        assert_eq!(
+            unsafe { libc::ftruncate(fd.as_raw_fd(), i64::from(STRIDE * 64)) },
+            0
+        );
+        DmatexPlane {
+            dmabuf_fd: fd.into(),
+            modifier: 0,
+            offset: 0,
+            stride: STRIDE as i32,
+        }
+    }
+
+    fn dmatex(res: Resolution, plane_count: usize) -> Dmatex {
+        Dmatex {
+            planes: (0..plane_count).map(|_| memfd_plane()).collect(),
+            res,
+            format: DrmFourcc::Argb8888 as u32,
+            transform: Default::default(),
+            srgb: false,
+            ycbcr_encoding: Default::default(),
+            ycbcr_range: Default::default(),
+            chroma_siting: Default::default(),
+            color_primaries: Default::default(),
+            transfer_function: Default::default(),
+            sync: Default::default(),
+        }
+    }
+
+    #[test]
+    fn memfd_is_not_a_dmabuf() {
+        let buf = dmatex(Resolution { x: 64, y: 64 }, 1);
+        assert_eq!(
+            validate_dmatex(&buf),
+            Err(ImportError::NotADmabuf.in_plane(0))
+        );
+    }
+
+    #[test]
+    fn invalid_resolution() {
+        for res in [
+            Resolution { x: 0, y: 64 },
+            Resolution { x: 64, y: 0 },
+            Resolution {
+                x: MAX_DMATEX_EXTENT + 1,
+                y: 64,
+            },
+            Resolution {
+                x: 64,
+                y: MAX_DMATEX_EXTENT + 1,
+            },
+        ] {
+            assert_eq!(
+                validate_dmatex(&dmatex(res, 1)),
+                Err(ImportError::InvalidResolution.into()),
+                "{res:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn plane_count() {
+        let res = Resolution { x: 64, y: 64 };
+        assert_eq!(
+            validate_dmatex(&dmatex(res, 0)),
+            Err(ImportError::NoPlanes.into())
+        );
+        assert_eq!(
+            validate_dmatex(&dmatex(res, MAX_DMATEX_PLANES + 1)),
+            Err(ImportError::IncorrectNumberOfPlanes.into())
+        );
+    }
+
+    #[test]
+    fn valid_udmabuf() {
+        let Some(plane) = udmabuf_plane(u64::from(STRIDE * 64)) else {
+            return;
+        };
+        let buf = Dmatex {
+            planes: vec![plane],
+            ..dmatex(Resolution { x: 64, y: 64 }, 0)
+        };
+        assert_eq!(validate_dmatex(&buf), Ok(()));
+    }
+
+    #[test]
+    fn dmabuf_too_small() {
+        let Some(plane) = udmabuf_plane(u64::from(STRIDE * 64)) else {
+            return;
+        };
+        let mut buf = Dmatex {
+            planes: vec![plane],
+            ..dmatex(Resolution { x: 64, y: 65 }, 0)
+        };
+        assert_eq!(
+            validate_dmatex(&buf),
+            Err(ImportError::DmabufTooSmall.in_plane(0))
+        );
+        buf.res.y = 64;
+        buf.planes[0].offset = STRIDE;
+        assert_eq!(
+            validate_dmatex(&buf),
+            Err(ImportError::DmabufTooSmall.in_plane(0))
+        );
+        // larger than a u32, checked without overflowing
+        buf.planes[0].offset = 0;
+        buf.planes[0].stride = i32::MAX;
+        buf.res.y = MAX_DMATEX_EXTENT;
+        assert_eq!(
+            validate_dmatex(&buf),
+            Err(ImportError::DmabufTooSmall.in_plane(0))
+        );
+    }
+
+    #[test]
+    fn invalid_plane_layout() {
+        let Some(plane) = udmabuf_plane(u64::from(STRIDE * 64)) else {
+            return;
+        };
+        let mut buf = Dmatex {
+            planes: vec![plane],
+            ..dmatex(Resolution { x: 64, y: 64 }, 0)
+        };
+        for (offset, stride) in [(0, 0), (0, -(STRIDE as i32)), (STRIDE * 64, STRIDE as i32)] {
+            buf.planes[0].offset = offset;
+            buf.planes[0].stride = stride;
+            assert_eq!(
+                validate_dmatex(&buf),
+                Err(ImportError::InvalidPlaneLayout.in_plane(0)),
+                "offset {offset}, stride {stride}"
+            );
+        }
+    }
+}
@@ -30,7 +30,7 @@ use std::sync::Arc;
 
 use ash::vk::PhysicalDeviceType;
 use bevy::app::{Plugin, PluginGroup, PluginGroupBuilder};
-use tracing::debug;
+use tracing::{debug, warn};
 use bevy::render::renderer::{
     RenderAdapter, RenderAdapterInfo, RenderInstance, RenderQueue, WgpuWrapper,
 };
@@ -40,7 +40,7 @@ use color_eyre::eyre::bail;
 use wgpu::hal::Api;
 use wgpu::hal::api::Vulkan;
 
-use crate::required_device_extensions;
+use crate::{optional_device_extensions, required_device_extensions};
 
 #[cfg(not(target_os = "android"))]
 const VK_TARGET_VERSION_ASH: u32 = ash::vk::make_api_version(0, 1, 2, 0);
@@ -169,6 +169,18 @@ fn init_graphics() -> color_eyre::Result<(
         };
         phys_dev
     };
+    let supported_extensions =
+        unsafe { vk_instance.enumerate_device_extension_properties(vk_physical_device)? };
+    for extension in optional_device_extensions() {
+        if supported_extensions
+            .iter()
+            .any(|v| v.extension_name_as_c_str().is_ok_and(|v| v == extension))
+        {
+            device_extensions.push(extension);
+        } else {
+            warn!("optional device extension {extension:?} is not supported");
+        }
+    }
     let Some(wgpu_exposed_adapter) = wgpu_vk_instance.expose_adapter(vk_physical_device) else {
         bail!("WGPU failed to provide an adapter");
     };